@@ -0,0 +1,81 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::consts::APP_NAME;
+use crate::opener::Opener;
+use crate::Entry;
+use crate::Error;
+use crate::Result;
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Choices {
+    // The apps chosen by the user for different file extensions
+    #[serde(default)]
+    pub apps: HashMap<String, Opener>,
+}
+
+impl Choices {
+    pub fn acquire() -> Result<Self> {
+        match choices_path() {
+            Some(choices_path) => match fs::read_to_string(&choices_path) {
+                Ok(raw) => match toml::from_str(&raw) {
+                    Ok(choices) => Ok(choices),
+                    Err(err) => Err(Error::new(&format!(
+                        "Invalid choices file '{}'! Reason: {}",
+                        choices_path.display(),
+                        err
+                    ))),
+                },
+                Err(_) => Ok(Choices::default()),
+            },
+            None => Ok(Choices::default()),
+        }
+    }
+    // Writes the choices to the data directory
+    pub fn save(&self) -> Result<()> {
+        let choices_path = match choices_path() {
+            Some(choices_path) => choices_path,
+            None => return Err(Error::new("Unable to determine choices path!")),
+        };
+        if let Some(parent) = choices_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // converting to a value first orders plain values before tables
+        match toml::Value::try_from(self).and_then(|value| toml::to_string(&value)) {
+            Ok(raw) => fs::write(choices_path, raw)?,
+            Err(err) => {
+                return Err(Error::new(&format!(
+                    "Unable to save choices! Reason: {}",
+                    err
+                )))
+            }
+        }
+        Ok(())
+    }
+    // Get app chosen for the extension of the entry
+    pub fn get_app(&self, entry: &Entry) -> Option<Opener> {
+        if entry.is_dir() {
+            return None;
+        }
+        self.apps.get(&entry.get_extension()).cloned()
+    }
+    // Remembers the app for the extension of the entry
+    pub fn remember(&mut self, entry: &Entry, opener: &Opener) -> Result<()> {
+        self.apps.insert(entry.get_extension(), opener.clone());
+        self.save()
+    }
+}
+
+fn choices_path() -> Option<PathBuf> {
+    match dirs::data_dir() {
+        Some(mut data_dir) => {
+            data_dir.push(APP_NAME);
+            data_dir.push("choices.toml");
+            Some(data_dir)
+        }
+        None => None,
+    }
+}
@@ -1,34 +1,84 @@
+use serde::de::value;
+use serde::de::IntoDeserializer;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 use std::path::PathBuf;
 
 use crate::consts::APP_NAME;
 use crate::consts::COLUMNS;
+use crate::dates::DateConfig;
+use crate::keys::Action;
+use crate::keys::Context;
+use crate::keys::Keymap;
+use crate::layout::ColumnLayout;
+use crate::opener::AppConfig;
+use crate::opener::Opener;
+use crate::sizes::SizeFormat;
+use crate::theme::Theme;
+use crate::theme::ThemeConfig;
+use crate::xdg;
 use crate::Column;
+use crate::Entry;
 use crate::Error;
 use crate::Result;
+use crate::Sort;
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct Config {
-    // The default app for opening files
+    // The default app for files which no opener matches, taking precedence
+    // over the associations of the system
     pub default: Option<String>,
     // The visible columns
     pub columns: Option<Vec<Column>>,
-    // The apps used for different file extensions
-    pub apps: Option<HashMap<String, Vec<String>>>,
+    // The widths, alignments and priorities overriding the defaults of the columns
+    pub layout: Option<HashMap<String, ColumnLayout>>,
+    // The flag if the name column fills the terminal width and low priority
+    // columns are dropped on narrow terminals
+    pub auto_layout: Option<bool>,
+    // The openers evaluated from top to bottom
+    pub openers: Option<Vec<Opener>>,
+    // The apps used for different file extensions, replaced by the openers
+    pub apps: Option<HashMap<String, AppConfig>>,
+    // The flag if the associations of the system are used when no opener matches
+    // and no default app is configured
+    pub xdg: Option<bool>,
+    // The key sequences bound to actions, replacing the default bindings
+    pub keys: Option<HashMap<String, Action>>,
+    // The single keys bound to actions in the open with menu
+    pub menu_keys: Option<HashMap<String, Action>>,
+    // The single keys bound to actions in the conflict dialog
+    pub dialog_keys: Option<HashMap<String, Action>>,
+    // The single keys bound to actions in the prompt
+    pub prompt_keys: Option<HashMap<String, Action>>,
+    // The styles of the ui elements
+    pub theme: Option<ThemeConfig>,
+    // The sort mode of the file list
+    pub sort: Option<Sort>,
+    // The flag if the file list is sorted in reverse
+    pub sort_reverse: Option<bool>,
+    // The flag if names are sorted ignoring their case
+    pub sort_ignore_case: Option<bool>,
+    // The units of sizes
+    pub size_format: Option<SizeFormat>,
+    // The formats of the date columns
+    pub dates: Option<DateConfig>,
 }
 
 impl Config {
     pub fn acquire() -> Result<Self> {
         match config_path() {
             Some(config_path) => match fs::read_to_string(config_path) {
-                Ok(raw) => match toml::from_str(&raw) {
-                    Ok(config) => Ok(config),
-                    Err(err) => {
-                        return Err(Error::new(&format!("Invalid config file! Reason: {}", err)))
-                    }
+                Ok(raw) => match toml::from_str::<Config>(&raw) {
+                    Ok(mut config) => match config.compile().and_then(|_| config.validate()) {
+                        Ok(_) => Ok(config),
+                        Err(err) => {
+                            Err(Error::new(&format!("Invalid config file! Reason: {}", err)))
+                        }
+                    },
+                    Err(err) => Err(Error::new(&format!("Invalid config file! Reason: {}", err))),
                 },
                 Err(_) => Ok(Config::default()),
             },
@@ -42,16 +92,138 @@ impl Config {
             None => COLUMNS.to_vec(),
         }
     }
-    // Get app for file extension
-    pub fn get_app(&self, file_ext: &str) -> Option<String> {
-        if self.apps.is_some() {
-            for (app, exts) in self.apps.as_ref().unwrap() {
-                if exts.contains(&file_ext.to_string().to_lowercase()) {
-                    return Some(app.clone());
+    // Get configured layouts of the columns
+    pub fn get_layouts(&self) -> Result<Vec<(Column, ColumnLayout)>> {
+        let mut layouts = Vec::new();
+        if let Some(layout) = &self.layout {
+            for (name, column_layout) in layout {
+                let deserializer: value::StrDeserializer<value::Error> =
+                    name.as_str().into_deserializer();
+                match Column::deserialize(deserializer) {
+                    Ok(column) => layouts.push((column, column_layout.clone())),
+                    Err(_) => return Err(Error::new(&format!("Invalid column '{}'!", name))),
                 }
             }
         }
-        self.default.clone()
+        Ok(layouts)
+    }
+    // Get keymap of the default and configured key bindings
+    pub fn get_keymap(&self) -> Result<Keymap> {
+        let contexts = [
+            (Context::Normal, &self.keys),
+            (Context::Menu, &self.menu_keys),
+            (Context::Dialog, &self.dialog_keys),
+            (Context::Prompt, &self.prompt_keys),
+        ];
+        let keys: Vec<(Context, &HashMap<String, Action>)> = contexts
+            .into_iter()
+            .filter_map(|(context, keys)| keys.as_ref().map(|keys| (context, keys)))
+            .collect();
+        Keymap::new(&keys)
+    }
+    // Get theme of the configured styles
+    pub fn get_theme(&self) -> Result<Theme> {
+        self.theme.clone().unwrap_or_default().get_theme()
+    }
+    // Get openers in the order they are evaluated, followed by the legacy
+    // apps sorted by name with extension matches preceding mime matches
+    pub fn get_openers(&self) -> Vec<Opener> {
+        let mut openers = self.openers.clone().unwrap_or_default();
+        if let Some(apps) = &self.apps {
+            let mut apps: Vec<(&String, &AppConfig)> = apps.iter().collect();
+            apps.sort_by(|a, b| a.0.cmp(b.0));
+            let mut by_mime = Vec::new();
+            for (app, config) in apps {
+                let opener = config.get_opener(app);
+                if !opener.mimes.is_empty() {
+                    by_mime.push(Opener {
+                        extensions: Vec::new(),
+                        ..opener.clone()
+                    });
+                }
+                if !opener.extensions.is_empty() {
+                    openers.push(Opener {
+                        mimes: Vec::new(),
+                        ..opener
+                    });
+                }
+            }
+            openers.extend(by_mime);
+        }
+        openers
+    }
+    // Get the first matching app for the entry, or the default app, or the
+    // app associated by the system
+    pub fn get_app(&self, entry: &Entry, dir: &Path) -> Option<Opener> {
+        let mut mime = None;
+        for opener in self.get_openers() {
+            if opener.matches(entry, dir, &mut mime) {
+                return Some(opener);
+            }
+        }
+        if let Some(app) = &self.default {
+            return Some(Opener::new(app));
+        }
+        self.get_system_apps(entry, dir, &mut mime)
+            .into_iter()
+            .next()
+    }
+    // Get all matching apps for the entry in order, followed by the default
+    // app and the apps associated by the system
+    pub fn get_apps(&self, entry: &Entry, dir: &Path) -> Vec<Opener> {
+        let mut mime = None;
+        let mut apps: Vec<Opener> = Vec::new();
+        for opener in self.get_openers() {
+            if opener.matches(entry, dir, &mut mime)
+                && !apps.iter().any(|app| app.app == opener.app)
+            {
+                apps.push(opener);
+            }
+        }
+        if let Some(app) = &self.default {
+            if !apps.iter().any(|other| &other.app == app) {
+                apps.push(Opener::new(app));
+            }
+        }
+        for opener in self.get_system_apps(entry, dir, &mut mime) {
+            if !apps.iter().any(|app| app.app == opener.app) {
+                apps.push(opener);
+            }
+        }
+        apps
+    }
+    // Get apps associated with the mime type of the entry by the system
+    fn get_system_apps(&self, entry: &Entry, dir: &Path, mime: &mut Option<String>) -> Vec<Opener> {
+        if !self.xdg.unwrap_or(true) || entry.is_dir() {
+            return Vec::new();
+        }
+        xdg::get_apps(mime.get_or_insert_with(|| entry.get_mime(dir)))
+    }
+    // Compiles the patterns of the openers once when the config is loaded
+    fn compile(&mut self) -> Result<()> {
+        for opener in self.openers.iter_mut().flatten() {
+            opener.compile()?;
+        }
+        for app in self.apps.iter_mut().flat_map(|apps| apps.values_mut()) {
+            if let AppConfig::Opener(opener) = app {
+                opener.compile()?;
+            }
+        }
+        Ok(())
+    }
+    // Checks the openers for missing apps, the keys for invalid bindings,
+    // the theme for invalid styles and the dates for invalid formats
+    fn validate(&self) -> Result<()> {
+        for opener in self.get_openers() {
+            opener.validate()?;
+        }
+        self.get_layouts()?;
+        self.get_keymap()?;
+        self.get_theme()?;
+        if let Some(dates) = &self.dates {
+            dates.validate()?;
+        }
+        Ok(())
     }
 }
 
@@ -1,10 +1,18 @@
+use std::time::Duration;
+
 use crate::Column;
 
 // The name of the app
 pub const APP_NAME: &str = "fx";
 // The number of lines excluding the file list
 pub const MARGIN: usize = 8;
+// The smallest width of the name column in the auto layout
+pub const MIN_NAME_WIDTH: usize = 16;
 // The offset for navigation up/down
 pub const PADDING: usize = 2;
 // The default visible columns
 pub const COLUMNS: [Column; 4] = [Column::Name, Column::Type, Column::Size, Column::Created];
+// The maximum number of undoable records in the journal
+pub const JOURNAL_SIZE: usize = 100;
+// The interval for updating the progress of running jobs
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
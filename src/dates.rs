@@ -0,0 +1,131 @@
+use chrono::format::Item;
+use chrono::format::StrftimeItems;
+use chrono::offset::Local;
+use chrono::offset::Utc;
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::TimeZone;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt::Display;
+use std::time::SystemTime;
+
+use crate::Column;
+use crate::Error;
+use crate::Result;
+
+// The format of dates unless another format is configured
+const DEFAULT_FORMAT: &str = "%d.%m.%Y %I:%M %P";
+// The format selecting relative times like `3 min ago`
+const RELATIVE: &str = "relative";
+// The format selecting ISO-8601 timestamps
+const ISO: &str = "iso";
+
+// The formats of the date columns, each either a strftime format like
+// `%Y-%m-%d %H:%M`, `relative` or `iso`
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DateConfig {
+    // The format of date columns without their own format
+    pub format: Option<String>,
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    pub accessed: Option<String>,
+    // The format of the deletion dates in the trash view
+    pub deleted: Option<String>,
+    // The flag if dates are shown in UTC instead of the local timezone
+    pub utc: bool,
+}
+
+impl DateConfig {
+    // Get format of the date column
+    pub fn get_format(&self, column: &Column) -> &str {
+        let format = match column {
+            Column::Created => &self.created,
+            Column::Modified => &self.modified,
+            Column::Accessed => &self.accessed,
+            _ => &None,
+        };
+        self.get_or_default(format)
+    }
+    // Get format of the deletion dates
+    pub fn get_deleted_format(&self) -> &str {
+        self.get_or_default(&self.deleted)
+    }
+    // Formats the time in the configured timezone
+    pub fn format_time(&self, time: SystemTime, format: &str) -> String {
+        match self.utc {
+            true => format_datetime(DateTime::<Utc>::from(time), Utc::now(), format),
+            false => format_datetime(DateTime::<Local>::from(time), Local::now(), format),
+        }
+    }
+    // Checks the strftime formats for invalid specifiers
+    pub fn validate(&self) -> Result<()> {
+        let formats = [
+            &self.format,
+            &self.created,
+            &self.modified,
+            &self.accessed,
+            &self.deleted,
+        ];
+        for format in formats.into_iter().flatten() {
+            if format == RELATIVE || format == ISO {
+                continue;
+            }
+            if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                return Err(Error::new(&format!("Invalid date format '{}'!", format)));
+            }
+        }
+        Ok(())
+    }
+    fn get_or_default<'a>(&'a self, format: &'a Option<String>) -> &'a str {
+        match (format, &self.format) {
+            (Some(format), _) | (None, Some(format)) => format,
+            (None, None) => DEFAULT_FORMAT,
+        }
+    }
+}
+
+fn format_datetime<Tz: TimeZone>(datetime: DateTime<Tz>, now: DateTime<Tz>, format: &str) -> String
+where
+    Tz::Offset: Display,
+{
+    match format {
+        RELATIVE => format_relative(datetime, now),
+        ISO => datetime.to_rfc3339_opts(SecondsFormat::Secs, true),
+        format => datetime.format(format).to_string(),
+    }
+}
+
+// Formats the time relative to now, where days are counted by the calendar
+fn format_relative<Tz: TimeZone>(datetime: DateTime<Tz>, now: DateTime<Tz>) -> String
+where
+    Tz::Offset: Display,
+{
+    let elapsed = now.clone().signed_duration_since(datetime.clone());
+    if elapsed.num_seconds() < 0 {
+        return datetime.format("%Y-%m-%d %H:%M").to_string();
+    }
+    let days = now
+        .naive_local()
+        .date()
+        .signed_duration_since(datetime.naive_local().date())
+        .num_days();
+    match days {
+        0 if elapsed.num_minutes() == 0 => "just now".to_string(),
+        0 if elapsed.num_hours() == 0 => format!("{} min ago", elapsed.num_minutes()),
+        0 => format!("{} h ago", elapsed.num_hours()),
+        1 => "yesterday".to_string(),
+        2..=6 => format!("{} days ago", days),
+        7..=29 => plural(days / 7, "week"),
+        30..=364 => plural(days / 30, "month"),
+        _ => plural(days / 365, "year"),
+    }
+}
+
+fn plural(count: i64, unit: &str) -> String {
+    match count {
+        1 => format!("1 {} ago", unit),
+        count => format!("{} {}s ago", count, unit),
+    }
+}
@@ -0,0 +1,258 @@
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::journal::Operation;
+use crate::Error;
+
+// The minimum time between two progress updates of a job
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum JobKind {
+    // Performs new operations, continuing after failed ones
+    Perform,
+    // Reverts the operations of an undone record in reverse order
+    Undo,
+    // Performs the operations of a redone record again
+    Redo,
+}
+
+struct Job {
+    id: usize,
+    dir: PathBuf,
+    kind: JobKind,
+    verb: String,
+    operations: Vec<Operation>,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Clone)]
+pub struct JobStatus {
+    // The number of copied bytes
+    pub bytes: u64,
+    // The total number of bytes to copy
+    pub total_bytes: u64,
+    // The number of copied files
+    pub files: usize,
+    // The total number of files to copy
+    pub total_files: usize,
+    // The time the job was started
+    pub started: Instant,
+}
+
+impl JobStatus {
+    // Get progress in percent
+    pub fn get_percent(&self) -> u64 {
+        match self.total_bytes {
+            0 => 100,
+            total_bytes => self.bytes * 100 / total_bytes,
+        }
+    }
+    // Get estimated remaining time based on the throughput so far
+    pub fn get_eta(&self) -> Option<Duration> {
+        if self.bytes == 0 {
+            return None;
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let remaining = self.total_bytes.saturating_sub(self.bytes) as f64;
+        Some(Duration::from_secs_f64(
+            elapsed * remaining / self.bytes as f64,
+        ))
+    }
+}
+
+pub struct JobResult {
+    // The directory the job was started in
+    pub dir: PathBuf,
+    // The kind of the job
+    pub kind: JobKind,
+    // The verb describing the job, or the description of the undone or redone record
+    pub verb: String,
+    // The operations which were performed or reverted successfully
+    pub operations: Vec<Operation>,
+    // The operations which were not performed or reverted, since the job
+    // failed or was cancelled before
+    pub pending: Vec<Operation>,
+    // The error of the last failed operation
+    pub error: Option<Error>,
+    // The flag if the job was cancelled
+    pub cancelled: bool,
+}
+
+enum JobEvent {
+    Status(JobStatus),
+    Done(usize, JobResult),
+}
+
+pub struct Jobs {
+    sender: Sender<Job>,
+    receiver: Receiver<JobEvent>,
+    // The cancel flags of unfinished jobs in queue order
+    pending: Vec<(usize, Arc<AtomicBool>)>,
+    next_id: usize,
+    // The status of the running job
+    pub status: Option<JobStatus>,
+}
+
+impl Jobs {
+    pub fn new() -> Self {
+        let (sender, jobs) = mpsc::channel::<Job>();
+        let (events, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for job in jobs {
+                run(job, &events);
+            }
+        });
+        Self {
+            sender,
+            receiver,
+            pending: Vec::new(),
+            next_id: 0,
+            status: None,
+        }
+    }
+    // Queues operations to be performed or reverted on the worker thread
+    pub fn push(&mut self, dir: PathBuf, kind: JobKind, verb: &str, operations: Vec<Operation>) {
+        if operations.is_empty() {
+            return;
+        }
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let job = Job {
+            id: self.next_id,
+            dir,
+            kind,
+            verb: verb.to_string(),
+            operations,
+            cancelled: cancelled.clone(),
+        };
+        if self.sender.send(job).is_ok() {
+            self.pending.push((self.next_id, cancelled));
+            self.next_id += 1;
+        }
+    }
+    // Cancels the running job
+    pub fn cancel(&self) -> bool {
+        match self.pending.first() {
+            Some((_, cancelled)) => {
+                cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+    // Get flag if any job is running or queued
+    pub fn is_busy(&self) -> bool {
+        !self.pending.is_empty()
+    }
+    // Get number of jobs waiting for the running job
+    pub fn get_queued(&self) -> usize {
+        self.pending.len().saturating_sub(1)
+    }
+    // Receives updates from the worker thread and returns the finished jobs,
+    // or none if nothing has changed
+    pub fn poll(&mut self) -> Option<Vec<JobResult>> {
+        let mut changed = false;
+        let mut finished = Vec::new();
+        while let Ok(event) = self.receiver.try_recv() {
+            changed = true;
+            match event {
+                JobEvent::Status(status) => self.status = Some(status),
+                JobEvent::Done(id, result) => {
+                    self.pending.retain(|(pending, _)| *pending != id);
+                    self.status = None;
+                    finished.push(result);
+                }
+            }
+        }
+        changed.then_some(finished)
+    }
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn run(job: Job, events: &Sender<JobEvent>) {
+    let revert = job.kind == JobKind::Undo;
+    let mut operations = job.operations;
+    // undone records are reverted starting with their last operation
+    if revert {
+        operations.reverse();
+    }
+    let sizes: Vec<(u64, usize)> = operations.iter().map(|op| op.measure(revert)).collect();
+    let mut status = JobStatus {
+        bytes: 0,
+        total_bytes: sizes.iter().map(|(bytes, _)| bytes).sum(),
+        files: 0,
+        total_files: sizes.iter().map(|(_, files)| files).sum(),
+        started: Instant::now(),
+    };
+    let _ = events.send(JobEvent::Status(status.clone()));
+    let cancelled = job.cancelled;
+    let mut updated = Instant::now();
+    let mut done = Vec::new();
+    let mut pending = Vec::new();
+    let mut error = None;
+    for (mut operation, (bytes, files)) in operations.into_iter().zip(sizes) {
+        // undone and redone records stop at the first failure, so that
+        // their operations are not performed out of order
+        if cancelled.load(Ordering::Relaxed) || (error.is_some() && job.kind != JobKind::Perform)
+        {
+            pending.push(operation);
+            continue;
+        }
+        let start = (status.bytes, status.files);
+        let mut progress = |bytes, files| {
+            if cancelled.load(Ordering::Relaxed) {
+                return Err(Error::new("Job cancelled!"));
+            }
+            status.bytes += bytes;
+            status.files += files;
+            if updated.elapsed() >= UPDATE_INTERVAL {
+                updated = Instant::now();
+                let _ = events.send(JobEvent::Status(status.clone()));
+            }
+            Ok(())
+        };
+        let result = match revert {
+            true => operation.revert_with(&mut progress),
+            false => operation.apply_with(&mut progress),
+        };
+        // moves within a filesystem do not report any progress
+        status.bytes = start.0 + bytes;
+        status.files = start.1 + files;
+        let _ = events.send(JobEvent::Status(status.clone()));
+        match result {
+            Ok(_) => done.push(operation),
+            Err(err) => {
+                error = Some(err);
+                pending.push(operation);
+            }
+        }
+    }
+    if revert {
+        done.reverse();
+        pending.reverse();
+    }
+    let cancelled = cancelled.load(Ordering::Relaxed);
+    let result = JobResult {
+        dir: job.dir,
+        kind: job.kind,
+        verb: job.verb,
+        operations: done,
+        pending,
+        error: if cancelled { None } else { error },
+        cancelled,
+    };
+    let _ = events.send(JobEvent::Done(job.id, result));
+}
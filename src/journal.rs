@@ -0,0 +1,388 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::ffi::OsStringExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::consts::APP_NAME;
+use crate::consts::JOURNAL_SIZE;
+use crate::ops;
+use crate::ops::Progress;
+use crate::trash;
+use crate::trash::TrashItem;
+use crate::Error;
+use crate::Result;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Operation {
+    Move {
+        #[serde(with = "raw_path")]
+        from: PathBuf,
+        #[serde(with = "raw_path")]
+        to: PathBuf,
+    },
+    Copy {
+        #[serde(with = "raw_path")]
+        from: PathBuf,
+        #[serde(with = "raw_path")]
+        to: PathBuf,
+    },
+    Rename {
+        #[serde(with = "raw_path")]
+        dir: PathBuf,
+        #[serde(with = "raw_names")]
+        names: Vec<(OsString, OsString)>,
+    },
+    CreateFile {
+        #[serde(with = "raw_path")]
+        path: PathBuf,
+    },
+    CreateDir {
+        #[serde(with = "raw_path")]
+        path: PathBuf,
+        #[serde(with = "raw_path")]
+        root: PathBuf,
+    },
+    CreateSymlink {
+        #[serde(with = "raw_path")]
+        target: PathBuf,
+        #[serde(with = "raw_path")]
+        path: PathBuf,
+    },
+    Trash {
+        #[serde(with = "raw_path")]
+        path: PathBuf,
+        name: String,
+    },
+    // An existing entry which is trashed to be overwritten
+    Replace {
+        #[serde(with = "raw_path")]
+        path: PathBuf,
+        name: String,
+    },
+}
+
+impl Operation {
+    // Get number of entries affected by the operation
+    pub fn count(&self) -> usize {
+        match self {
+            Self::Rename { names, .. } => names.len(),
+            Self::Replace { .. } => 0,
+            _ => 1,
+        }
+    }
+    // Get paths of the entries which are added or removed by the operation
+    pub fn get_paths(&self) -> Vec<PathBuf> {
+        match self {
+            Self::Move { from, to } => vec![from.clone(), to.clone()],
+            Self::Copy { to: path, .. }
+            | Self::CreateFile { path }
+            | Self::CreateDir { root: path, .. }
+            | Self::CreateSymlink { path, .. }
+            | Self::Trash { path, .. }
+            | Self::Replace { path, .. } => vec![path.clone()],
+            Self::Rename { dir, names } => names
+                .iter()
+                .flat_map(|(from, to)| [dir.join(from), dir.join(to)])
+                .collect(),
+        }
+    }
+    // Get total size in bytes and number of files which are copied by the operation,
+    // or by reverting it, where entries moved within a filesystem are not copied
+    pub fn measure(&self, revert: bool) -> (u64, usize) {
+        let files = trash::trash_dir()
+            .map(|trash_dir| trash_dir.join("files"))
+            .unwrap_or_default();
+        let (src, dest) = match (self, revert) {
+            (Self::Copy { from, .. }, false) => return ops::measure(from),
+            (Self::Move { from, to }, false) => (from.clone(), to.clone()),
+            (Self::Move { from, to }, true) => (to.clone(), from.clone()),
+            (Self::Trash { path, .. } | Self::Replace { path, .. }, false) => {
+                (path.clone(), files)
+            }
+            (Self::Trash { path, name } | Self::Replace { path, name }, true) => {
+                (files.join(name), path.clone())
+            }
+            (
+                Self::Copy { to: path, .. }
+                | Self::CreateFile { path }
+                | Self::CreateDir { root: path, .. }
+                | Self::CreateSymlink { path, .. },
+                true,
+            ) => (path.clone(), files),
+            _ => return (0, 0),
+        };
+        match ops::is_same_filesystem(&src, &dest) {
+            true => (0, 0),
+            false => ops::measure(&src),
+        }
+    }
+    // Performs the operation
+    pub fn apply(&mut self) -> Result<()> {
+        self.apply_with(&mut |_, _| Ok(()))
+    }
+    // Performs the operation and reports the progress of copied files
+    pub fn apply_with(&mut self, progress: &mut Progress) -> Result<()> {
+        match self {
+            Self::Move { from, to } => {
+                ensure_missing(to)?;
+                ops::rename_with(from, to, progress)
+            }
+            Self::Copy { from, to } => {
+                ensure_missing(to)?;
+                ops::copy_with(from, to, progress)
+            }
+            Self::Rename { dir, names } => ops::rename_all(dir, names).map(|_| ()),
+            Self::CreateFile { path } => ops::create_file(path),
+            Self::CreateDir { path, root } => {
+                *root = ops::create_dir(path)?;
+                Ok(())
+            }
+            Self::CreateSymlink { target, path } => ops::create_symlink(target, path),
+            Self::Trash { path, name } | Self::Replace { path, name } => {
+                *name = trash::put_with(path, progress)?.name;
+                Ok(())
+            }
+        }
+    }
+    // Reverts the effect of the operation
+    pub fn revert(&self) -> Result<()> {
+        self.revert_with(&mut |_, _| Ok(()))
+    }
+    // Reverts the effect of the operation and reports the progress of copied files
+    pub fn revert_with(&self, progress: &mut Progress) -> Result<()> {
+        match self {
+            Self::Move { from, to } => {
+                ensure_missing(from)?;
+                ops::rename_with(to, from, progress)
+            }
+            // created entries are trashed, so that changes made to them are not lost
+            Self::Copy { to: path, .. }
+            | Self::CreateFile { path }
+            | Self::CreateDir { root: path, .. }
+            | Self::CreateSymlink { path, .. } => trash::put_with(path, progress).map(|_| ()),
+            Self::Rename { dir, names } => {
+                let names: Vec<(OsString, OsString)> = names
+                    .iter()
+                    .map(|(from, to)| (to.clone(), from.clone()))
+                    .collect();
+                ops::rename_all(dir, &names).map(|_| ())
+            }
+            Self::Trash { path, name } | Self::Replace { path, name } => trash::restore_with(
+                &TrashItem {
+                    name: name.clone(),
+                    path: path.clone(),
+                    deleted: None,
+                },
+                progress,
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    // The description shown when undoing or redoing the record
+    pub description: String,
+    // The operations performed together
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Journal {
+    // The records which can be undone
+    pub undo: Vec<Record>,
+    // The records which can be redone
+    pub redo: Vec<Record>,
+}
+
+impl Journal {
+    // Reads the journal, failing on a corrupt journal instead of overwriting it later
+    pub fn acquire() -> Result<Self> {
+        match journal_path() {
+            Some(journal_path) => match fs::read_to_string(&journal_path) {
+                Ok(raw) => match toml::from_str(&raw) {
+                    Ok(journal) => Ok(journal),
+                    Err(err) => Err(Error::new(&format!(
+                        "Invalid journal file '{}'! Reason: {}",
+                        journal_path.display(),
+                        err
+                    ))),
+                },
+                Err(_) => Ok(Journal::default()),
+            },
+            None => Ok(Journal::default()),
+        }
+    }
+    // Writes the journal to the data directory
+    pub fn save(&self) -> Result<()> {
+        let journal_path = match journal_path() {
+            Some(journal_path) => journal_path,
+            None => return Err(Error::new("Unable to determine journal path!")),
+        };
+        if let Some(parent) = journal_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // converting to a value first orders plain values before tables
+        match toml::Value::try_from(self).and_then(|value| toml::to_string(&value)) {
+            Ok(raw) => fs::write(journal_path, raw)?,
+            Err(err) => {
+                return Err(Error::new(&format!(
+                    "Unable to save journal! Reason: {}",
+                    err
+                )))
+            }
+        }
+        Ok(())
+    }
+    // Adds performed operations to the journal
+    pub fn record(&mut self, description: &str, operations: Vec<Operation>) -> Result<()> {
+        if operations.is_empty() {
+            return Ok(());
+        }
+        let record = Record {
+            description: description.to_string(),
+            operations,
+        };
+        self.undo.push(record);
+        if self.undo.len() > JOURNAL_SIZE {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.save()
+    }
+    // Takes the last record to undo, or to redo, off the journal while it is performed
+    pub fn pop(&mut self, redo: bool) -> Option<Record> {
+        match redo {
+            true => self.redo.pop(),
+            false => self.undo.pop(),
+        }
+    }
+    // Puts the undone or redone operations of the taken record on the opposite
+    // stack, while the pending ones stay on their stack to be tried again
+    pub fn complete(
+        &mut self,
+        redo: bool,
+        description: &str,
+        done: Vec<Operation>,
+        pending: Vec<Operation>,
+    ) -> Result<()> {
+        let record = |operations| Record {
+            description: description.to_string(),
+            operations,
+        };
+        let (done_stack, pending_stack) = match redo {
+            true => (&mut self.undo, &mut self.redo),
+            false => (&mut self.redo, &mut self.undo),
+        };
+        done_stack.push(record(done));
+        pending_stack.push(record(pending));
+        if self.undo.len() > JOURNAL_SIZE {
+            self.undo.remove(0);
+        }
+        self.discard_empty();
+        self.save()
+    }
+    // Removes records left without operations after a partial undo or redo
+    fn discard_empty(&mut self) {
+        self.undo.retain(|record| !record.operations.is_empty());
+        self.redo.retain(|record| !record.operations.is_empty());
+    }
+}
+
+fn ensure_missing(path: &Path) -> Result<()> {
+    if path.symlink_metadata().is_ok() {
+        return Err(Error::new(&format!("'{}' already exists!", path.display())));
+    }
+    Ok(())
+}
+
+fn journal_path() -> Option<PathBuf> {
+    match dirs::data_dir() {
+        Some(mut data_dir) => {
+            data_dir.push(APP_NAME);
+            data_dir.push("journal.toml");
+            Some(data_dir)
+        }
+        None => None,
+    }
+}
+
+// The name or path in the journal, which is text if it is valid UTF-8
+// and its raw bytes otherwise
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawName {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl RawName {
+    fn new(name: &OsStr) -> Self {
+        match name.to_str() {
+            Some(text) => Self::Text(text.to_string()),
+            None => Self::Bytes(name.as_bytes().to_vec()),
+        }
+    }
+    fn into_os_string(self) -> OsString {
+        match self {
+            Self::Text(text) => text.into(),
+            Self::Bytes(bytes) => OsString::from_vec(bytes),
+        }
+    }
+}
+
+mod raw_path {
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use super::RawName;
+
+    pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        RawName::new(path.as_os_str()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+        Ok(RawName::deserialize(deserializer)?.into_os_string().into())
+    }
+}
+
+mod raw_names {
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+    use std::ffi::OsString;
+
+    use super::RawName;
+
+    pub fn serialize<S: Serializer>(
+        names: &[(OsString, OsString)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let names: Vec<(RawName, RawName)> = names
+            .iter()
+            .map(|(from, to)| (RawName::new(from), RawName::new(to)))
+            .collect();
+        names.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(OsString, OsString)>, D::Error> {
+        let names: Vec<(RawName, RawName)> = Vec::deserialize(deserializer)?;
+        Ok(names
+            .into_iter()
+            .map(|(from, to)| (from.into_os_string(), to.into_os_string()))
+            .collect())
+    }
+}
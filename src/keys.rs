@@ -0,0 +1,330 @@
+use console::Key;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::Error;
+use crate::Result;
+
+// The default key sequences and their actions in the contexts they apply to
+const BINDINGS: [(Context, &str, Action); 68] = [
+    (Context::Normal, "q", Action::Quit),
+    (Context::Normal, "j", Action::Down),
+    (Context::Normal, "k", Action::Up),
+    (Context::Normal, "h", Action::Parent),
+    (Context::Normal, "l", Action::Child),
+    (Context::Normal, ".", Action::ToggleDotfiles),
+    (Context::Normal, ",", Action::Repeat),
+    (Context::Normal, "x", Action::ToggleSelect),
+    (Context::Normal, "%", Action::SelectAll),
+    (Context::Normal, "X", Action::ClearSelection),
+    (Context::Normal, "n", Action::NextSelected),
+    (Context::Normal, "N", Action::PrevSelected),
+    (Context::Normal, "g g", Action::Top),
+    (Context::Normal, "g e", Action::Bottom),
+    (Context::Normal, "~", Action::Home),
+    (Context::Normal, "t", Action::Goto),
+    (Context::Normal, "/", Action::Search),
+    (Context::Normal, "r", Action::Reload),
+    (Context::Normal, "S", Action::CycleSort),
+    (Context::Normal, "O", Action::ReverseSort),
+    (Context::Normal, "i", Action::ToggleIgnoreCase),
+    (Context::Normal, "z", Action::DirSizes),
+    (Context::Normal, "enter", Action::Open),
+    (Context::Normal, "o", Action::OpenWith),
+    (Context::Normal, "y", Action::Yank),
+    (Context::Normal, "d", Action::Cut),
+    (Context::Normal, "p", Action::Paste),
+    (Context::Normal, "c", Action::Rename),
+    (Context::Normal, "C", Action::BulkRename),
+    (Context::Normal, "a", Action::NewFile),
+    (Context::Normal, "A", Action::NewDir),
+    (Context::Normal, "s", Action::Symlink),
+    (Context::Normal, "D", Action::Delete),
+    (Context::Normal, "T", Action::TrashView),
+    (Context::Normal, "R", Action::Restore),
+    (Context::Normal, "u", Action::Undo),
+    (Context::Normal, "U", Action::Redo),
+    (Context::Normal, "K", Action::CancelJob),
+    (Context::Normal, ":", Action::Command),
+    (Context::Normal, "!", Action::Shell),
+    (Context::Normal, "esc", Action::Escape),
+    (Context::Menu, "enter", Action::Confirm),
+    (Context::Menu, "r", Action::Remember),
+    (Context::Menu, "j", Action::Down),
+    (Context::Menu, "down", Action::Down),
+    (Context::Menu, "k", Action::Up),
+    (Context::Menu, "up", Action::Up),
+    (Context::Menu, "q", Action::Quit),
+    (Context::Menu, "esc", Action::Escape),
+    (Context::Dialog, "o", Action::Overwrite),
+    (Context::Dialog, "O", Action::OverwriteAll),
+    (Context::Dialog, "s", Action::Skip),
+    (Context::Dialog, "S", Action::SkipAll),
+    (Context::Dialog, "r", Action::Rename),
+    (Context::Dialog, "R", Action::RenameAll),
+    (Context::Dialog, "n", Action::Newer),
+    (Context::Dialog, "N", Action::NewerAll),
+    (Context::Dialog, "esc", Action::Escape),
+    (Context::Prompt, "enter", Action::Confirm),
+    (Context::Prompt, "esc", Action::Escape),
+    (Context::Prompt, "backspace", Action::DeleteBack),
+    (Context::Prompt, "del", Action::DeleteForward),
+    (Context::Prompt, "left", Action::CursorLeft),
+    (Context::Prompt, "right", Action::CursorRight),
+    (Context::Prompt, "home", Action::CursorStart),
+    (Context::Prompt, "end", Action::CursorEnd),
+    (Context::Prompt, "up", Action::HistoryPrev),
+    (Context::Prompt, "down", Action::HistoryNext),
+];
+
+// The names of keys which are not written as a single character
+const NAMES: [(&str, Key); 15] = [
+    ("enter", Key::Enter),
+    ("esc", Key::Escape),
+    ("backspace", Key::Backspace),
+    ("del", Key::Del),
+    ("tab", Key::Tab),
+    ("backtab", Key::BackTab),
+    ("space", Key::Char(' ')),
+    ("up", Key::ArrowUp),
+    ("down", Key::ArrowDown),
+    ("left", Key::ArrowLeft),
+    ("right", Key::ArrowRight),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+];
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    // Removes the default binding of a key sequence
+    None,
+    Quit,
+    Down,
+    Up,
+    Parent,
+    Child,
+    ToggleDotfiles,
+    ToggleSelect,
+    SelectAll,
+    ClearSelection,
+    NextSelected,
+    PrevSelected,
+    Top,
+    Bottom,
+    Home,
+    Goto,
+    Search,
+    Reload,
+    CycleSort,
+    ReverseSort,
+    // Toggles if names are sorted ignoring their case
+    ToggleIgnoreCase,
+    // Computes the recursive sizes of directories
+    DirSizes,
+    Open,
+    OpenWith,
+    Yank,
+    Cut,
+    Paste,
+    Rename,
+    BulkRename,
+    NewFile,
+    NewDir,
+    Symlink,
+    // Trashes entries, or purges them in the trash view
+    Delete,
+    TrashView,
+    // Restores entries in the trash view
+    Restore,
+    Undo,
+    Redo,
+    CancelJob,
+    Command,
+    Shell,
+    // Performs the last action again
+    Repeat,
+    // Leaves the trash view, or aborts the menu, dialog or prompt
+    Escape,
+    // Uses the current menu item or the input of the prompt
+    Confirm,
+    // Uses the current menu item and remembers it
+    Remember,
+    // Resolves the conflict of the dialog, or all conflicts of the operation
+    Overwrite,
+    OverwriteAll,
+    Skip,
+    SkipAll,
+    RenameAll,
+    Newer,
+    NewerAll,
+    // Edits the input of the prompt
+    DeleteBack,
+    DeleteForward,
+    CursorLeft,
+    CursorRight,
+    CursorStart,
+    CursorEnd,
+    HistoryPrev,
+    HistoryNext,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Context {
+    // The file list
+    Normal,
+    // The open with menu
+    Menu,
+    // The dialog resolving conflicts
+    Dialog,
+    // The prompt, where unbound characters are typed into the input
+    Prompt,
+}
+
+pub enum Binding {
+    // The key sequence is bound to the action
+    Action(Action),
+    // The key sequence is the start of a longer bound sequence
+    Prefix,
+    // The key sequence is not bound
+    Unbound,
+}
+
+pub struct Keymap {
+    // The key sequences and their actions in the contexts they apply to
+    bindings: Vec<(Context, Vec<Key>, Action)>,
+}
+
+impl Keymap {
+    // Creates the keymap from the default bindings, which are replaced by
+    // the configured bindings of the same or of overlapping key sequences
+    // in the same context
+    pub fn new(keys: &[(Context, &HashMap<String, Action>)]) -> Result<Self> {
+        let mut configured: Vec<(Context, Vec<Key>, Action)> = Vec::new();
+        for (context, keys) in keys {
+            for (sequence, action) in *keys {
+                let parsed = parse_sequence(sequence)?;
+                // only the file list waits for longer key sequences
+                if *context != Context::Normal && parsed.len() > 1 {
+                    return Err(Error::new(&format!(
+                        "Key '{}' must be a single key!",
+                        sequence
+                    )));
+                }
+                for (other_context, other, _) in &configured {
+                    if other_context == context && overlaps(&parsed, other) {
+                        return Err(Error::new(&format!(
+                            "Key '{}' conflicts with key '{}'!",
+                            sequence,
+                            format_sequence(other)
+                        )));
+                    }
+                }
+                configured.push((*context, parsed, *action));
+            }
+        }
+        let mut bindings: Vec<(Context, Vec<Key>, Action)> = BINDINGS
+            .iter()
+            .map(|(context, sequence, action)| {
+                (
+                    *context,
+                    parse_sequence(sequence).unwrap_or_default(),
+                    *action,
+                )
+            })
+            .filter(|(context, keys, _)| {
+                !configured.iter().any(|(other_context, other, _)| {
+                    other_context == context && overlaps(keys, other)
+                })
+            })
+            .collect();
+        bindings.extend(configured);
+        bindings.retain(|(_, _, action)| *action != Action::None);
+        Ok(Self { bindings })
+    }
+    // Get the binding of the keys pressed so far in the context
+    pub fn lookup(&self, context: Context, keys: &[Key]) -> Binding {
+        let mut binding = Binding::Unbound;
+        for (other_context, sequence, action) in &self.bindings {
+            if *other_context != context {
+                continue;
+            }
+            if sequence == keys {
+                return Binding::Action(*action);
+            }
+            if sequence.starts_with(keys) {
+                binding = Binding::Prefix;
+            }
+        }
+        binding
+    }
+    // Get action of the single key in the context
+    pub fn get_action(&self, context: Context, key: &Key) -> Option<Action> {
+        match self.lookup(context, std::slice::from_ref(key)) {
+            Binding::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+    // Get first key sequence bound to the action in the context
+    pub fn get_sequence(&self, context: Context, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|(other_context, _, other)| *other_context == context && *other == action)
+            .map(|(_, keys, _)| format_sequence(keys))
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&[]).unwrap_or(Self {
+            bindings: Vec::new(),
+        })
+    }
+}
+
+// Parses a key sequence like `g g` or `enter` into keys
+pub fn parse_sequence(sequence: &str) -> Result<Vec<Key>> {
+    let mut keys = Vec::new();
+    for name in sequence.split_whitespace() {
+        let mut chars = name.chars();
+        let key = match (chars.next(), chars.next()) {
+            (Some(char), None) => Key::Char(char),
+            _ => match NAMES
+                .iter()
+                .find(|(other, _)| other.eq_ignore_ascii_case(name))
+            {
+                Some((_, key)) => key.clone(),
+                None => return Err(Error::new(&format!("Invalid key '{}'!", name))),
+            },
+        };
+        keys.push(key);
+    }
+    if keys.is_empty() {
+        return Err(Error::new("Empty key sequence!"));
+    }
+    Ok(keys)
+}
+
+// Formats keys as a key sequence like `g g` or `enter`
+pub fn format_sequence(keys: &[Key]) -> String {
+    let names: Vec<String> = keys
+        .iter()
+        .map(|key| match NAMES.iter().find(|(_, other)| other == key) {
+            Some((name, _)) => name.to_string(),
+            None => match key {
+                Key::Char(char) => char.to_string(),
+                _ => "?".to_string(),
+            },
+        })
+        .collect();
+    names.join(" ")
+}
+
+// Checks if one key sequence is the start of the other, so that the longer
+// one could never be reached
+fn overlaps(a: &[Key], b: &[Key]) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
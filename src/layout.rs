@@ -0,0 +1,51 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::text;
+
+// The ellipsis replacing the middle of long names
+const ELLIPSIS: char = '…';
+// The gap between two columns
+const GAP: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ColumnLayout {
+    // The width of the column including the gap to the next column
+    pub width: Option<usize>,
+    // The alignment of the values
+    pub align: Option<Align>,
+    // The priority of the column in the auto layout, where columns with
+    // the lowest priority are dropped first
+    pub priority: Option<usize>,
+}
+
+// Fits the text into a column, either cutting it off or replacing its middle
+// by an ellipsis if it is too long
+pub fn fit(text: &str, width: usize, align: Align, ellipsize: bool) -> String {
+    let max_width = width.saturating_sub(GAP);
+    let text = match text::width(text) > max_width {
+        true if ellipsize && max_width > 1 => {
+            let head = max_width / 2;
+            let tail = max_width - head - 1;
+            format!(
+                "{}{}{}",
+                text::truncate(text, head),
+                ELLIPSIS,
+                text::truncate_start(text, tail)
+            )
+        }
+        true => text::truncate(text, max_width),
+        false => text.to_string(),
+    };
+    match align {
+        Align::Left => text::pad(&text, width),
+        Align::Right => text::pad(&text::pad_start(&text, max_width), width),
+    }
+}
@@ -1,20 +1,45 @@
-use console::Color;
 use serde::Deserialize;
 use serde::Serialize;
+use std::ffi::OsString;
 use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 use std::time::SystemTime;
 
+use layout::Align;
+
+mod choices;
 mod config;
 mod state;
 
 pub mod consts;
+pub mod dates;
 pub mod error;
+pub mod jobs;
+pub mod journal;
+pub mod keys;
+pub mod layout;
+pub mod mime;
+pub mod opener;
+pub mod ops;
+pub mod shell;
+pub mod sizes;
+pub mod sort;
+pub mod text;
+pub mod theme;
+pub mod trash;
+pub mod users;
+pub mod xdg;
 
 use error::Error;
 
+pub use choices::Choices;
 pub use config::Config;
+pub use journal::Journal;
 pub use state::State;
 
 #[macro_export]
@@ -30,18 +55,10 @@ macro_rules! color {
 #[macro_export]
 macro_rules! pad {
     ($str:expr, $width:expr$(,)?) => {
-        &format!("{: <width$}", $str, width = $width)
+        &$crate::text::pad(&$str, $width)
     };
     ($str:expr, $width:expr, $max_width:expr$(,)?) => {
-        &format!(
-            "{: <width$}",
-            if $str.len() > $max_width {
-                $str.split_at($max_width).0.to_string()
-            } else {
-                $str.to_string()
-            },
-            width = $width
-        )
+        &$crate::text::pad(&$crate::text::truncate(&$str, $max_width), $width)
     };
 }
 
@@ -51,9 +68,19 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Mode {
     Normal,
     Prompt,
+    Dialog,
+    Menu,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Resolution {
+    Overwrite,
+    Skip,
+    Rename,
+    OverwriteIfNewer,
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Move {
     Up,
     Down,
@@ -71,68 +98,171 @@ pub enum FolderDir {
     Home,
 }
 
-#[derive(Clone, PartialEq)]
+pub enum Clipboard {
+    Copy(Vec<PathBuf>),
+    Cut(Vec<PathBuf>),
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum EntryKind {
+    #[default]
     File,
     Dir,
     Symlink,
 }
 
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
 pub struct Message {
-    text: String,
-    color: Color,
+    pub text: String,
+    pub level: Level,
 }
 
 impl Message {
     pub fn info(text: &str) -> Self {
         Self {
             text: text.to_string(),
-            color: Color::White,
+            level: Level::Info,
         }
     }
     pub fn warn(text: &str) -> Self {
         Self {
             text: text.to_string(),
-            color: Color::Yellow,
+            level: Level::Warn,
         }
     }
     pub fn error(text: &str) -> Self {
         Self {
             text: text.to_string(),
-            color: Color::Red,
+            level: Level::Error,
         }
     }
 }
 
-impl fmt::Display for Message {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", color!(&self.text, self.color))
-    }
-}
-
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Entry {
+    // The name shown on screen, where bytes which are not valid UTF-8 are escaped
     pub file_name: String,
+    // The raw name used for accessing the entry on the filesystem
+    pub os_name: OsString,
     pub kind: EntryKind,
     pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
     pub size: usize,
+    // The file type and permission bits of the entry itself
+    pub mode: u32,
+    // The user and group ids of the owner
+    pub uid: u32,
+    pub gid: u32,
+    pub inode: u64,
+    // The number of hard links
+    pub links: u64,
+    // The target path if the entry is a symlink
+    pub target: Option<PathBuf>,
+    // The flag if the entry is a symlink to a missing target
+    pub broken: bool,
 }
 
 impl Entry {
+    // Reads the metadata of the entry at the path, not following symlinks
+    pub fn read(os_name: OsString, path: &Path) -> io::Result<Self> {
+        let metadata = path.symlink_metadata()?;
+        let mut kind = EntryKind::File;
+        if metadata.is_dir() {
+            kind = EntryKind::Dir;
+        }
+        let mut target = None;
+        let mut broken = false;
+        if metadata.is_symlink() {
+            kind = EntryKind::Symlink;
+            target = fs::read_link(path).ok();
+            broken = fs::metadata(path).is_err();
+        }
+        Ok(Self {
+            file_name: text::escape(&os_name),
+            os_name,
+            kind,
+            created: metadata.created().ok(),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            size: metadata.len() as usize,
+            mode: metadata.mode(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            inode: metadata.ino(),
+            links: metadata.nlink(),
+            target,
+            broken,
+        })
+    }
+    // Get path of the entry in the directory
+    pub fn get_path(&self, dir: &Path) -> PathBuf {
+        dir.join(&self.os_name)
+    }
     pub fn is_dir(&self) -> bool {
         EntryKind::Dir.eq(&self.kind)
     }
     pub fn is_file(&self) -> bool {
         EntryKind::File.eq(&self.kind)
     }
+    // Get lowercase file extension, which is the whole name for names without a dot
+    pub fn get_extension(&self) -> String {
+        let file_ext = self.file_name.rsplit('.').next().unwrap_or_default();
+        file_ext.to_lowercase()
+    }
+    // Get permissions like `drwxr-xr-x 755`
+    pub fn get_permissions(&self) -> String {
+        let mode = self.mode;
+        let file_type = match mode & libc::S_IFMT {
+            libc::S_IFDIR => 'd',
+            libc::S_IFLNK => 'l',
+            libc::S_IFIFO => 'p',
+            libc::S_IFSOCK => 's',
+            libc::S_IFBLK => 'b',
+            libc::S_IFCHR => 'c',
+            _ => '-',
+        };
+        let mut permissions = String::from(file_type);
+        // the special bit replaces the execute bit of its class
+        let classes = [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')];
+        for (shift, special, char) in classes {
+            let bits = mode >> shift;
+            permissions.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+            permissions.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+            permissions.push(match (bits & 0o1 != 0, mode & special != 0) {
+                (true, true) => char,
+                (false, true) => char.to_ascii_uppercase(),
+                (true, false) => 'x',
+                (false, false) => '-',
+            });
+        }
+        format!("{} {:o}", permissions, mode & 0o7777)
+    }
+    // Get mime type by sniffing the content of the entry
+    pub fn get_mime(&self, dir: &Path) -> String {
+        mime::detect(&self.get_path(dir))
+    }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum Column {
     Name,
     Type,
     Size,
     Created,
+    Modified,
+    Accessed,
+    Permissions,
+    Owner,
+    Group,
+    Inode,
+    Links,
+    Target,
 }
 
 impl Column {
@@ -142,6 +272,39 @@ impl Column {
             Self::Type => 10,
             Self::Size => 15,
             Self::Created => 22,
+            Self::Modified => 22,
+            Self::Accessed => 22,
+            Self::Permissions => 17,
+            Self::Owner => 12,
+            Self::Group => 12,
+            Self::Inode => 12,
+            Self::Links => 7,
+            Self::Target => 40,
+        }
+    }
+    // Get default alignment of the values, where numbers are right-aligned
+    pub fn get_align(&self) -> Align {
+        match *self {
+            Self::Size | Self::Inode | Self::Links => Align::Right,
+            _ => Align::Left,
+        }
+    }
+    // Get default priority in the auto layout, where columns with the lowest
+    // priority are dropped first on narrow terminals
+    pub fn get_priority(&self) -> usize {
+        match *self {
+            Self::Name => 100,
+            Self::Size => 90,
+            Self::Modified => 80,
+            Self::Type => 70,
+            Self::Created => 60,
+            Self::Permissions => 50,
+            Self::Owner => 40,
+            Self::Group => 30,
+            Self::Target => 20,
+            Self::Links => 15,
+            Self::Inode => 10,
+            Self::Accessed => 5,
         }
     }
 }
@@ -156,6 +319,68 @@ impl fmt::Display for Column {
                 Self::Type => "TYPE",
                 Self::Size => "SIZE",
                 Self::Created => "CREATED",
+                Self::Modified => "MODIFIED",
+                Self::Accessed => "ACCESSED",
+                Self::Permissions => "PERMISSIONS",
+                Self::Owner => "OWNER",
+                Self::Group => "GROUP",
+                Self::Inode => "INODE",
+                Self::Links => "LINKS",
+                Self::Target => "TARGET",
+            }
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    // Groups dirs, symlinks and files, each sorted by name
+    Type,
+    Name,
+    Extension,
+    Size,
+    Created,
+    Modified,
+}
+
+impl Sort {
+    // Get the sort mode following this one when cycling through them
+    pub fn next(&self) -> Self {
+        match *self {
+            Self::Type => Self::Name,
+            Self::Name => Self::Extension,
+            Self::Extension => Self::Size,
+            Self::Size => Self::Created,
+            Self::Created => Self::Modified,
+            Self::Modified => Self::Type,
+        }
+    }
+    // Get the column showing the sort key, if there is one
+    pub fn get_column(&self) -> Option<Column> {
+        match *self {
+            Self::Type => Some(Column::Type),
+            Self::Name => Some(Column::Name),
+            Self::Size => Some(Column::Size),
+            Self::Created => Some(Column::Created),
+            Self::Modified => Some(Column::Modified),
+            Self::Extension => None,
+        }
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Self::Type => "type",
+                Self::Name => "name",
+                Self::Extension => "extension",
+                Self::Size => "size",
+                Self::Created => "created",
+                Self::Modified => "modified",
             }
         )
     }
@@ -168,12 +393,65 @@ pub fn expand_tilde(path: PathBuf) -> Option<PathBuf> {
     if path == Path::new("~") {
         return dirs::home_dir();
     }
-    let mut home = match dirs::home_dir() {
-        Some(home) => home,
-        None => return None,
-    };
+    let mut home = dirs::home_dir()?;
     for item in path.iter().skip(1) {
         home.push(item);
     }
     Some(home)
 }
+
+// The terminal mode which delivers single keys without waiting for a newline,
+// restoring the previous mode when dropped
+pub struct KeyMode {
+    // The mode before keys were delivered one by one
+    original: Option<libc::termios>,
+}
+
+impl KeyMode {
+    // Turns off line buffering and echo of stdin, output processing stays on
+    // so that the screen can be printed while waiting for a key
+    pub fn enable() -> Self {
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Self { original: None };
+        }
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        match unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } {
+            0 => Self {
+                original: Some(original),
+            },
+            _ => Self { original: None },
+        }
+    }
+}
+
+impl Drop for KeyMode {
+    fn drop(&mut self) {
+        if let Some(original) = &self.original {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original) };
+        }
+    }
+}
+
+// Waits until input is available on stdin or the timeout has passed,
+// which requires the key mode since a line buffered tty only reports full lines
+pub fn poll_input(timeout: Duration) -> io::Result<bool> {
+    let mut pollfd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let ret = unsafe { libc::poll(&mut pollfd, 1, timeout.as_millis() as libc::c_int) };
+    if ret < 0 {
+        let err = io::Error::last_os_error();
+        // a signal like a terminal resize interrupts the poll
+        return match err.kind() {
+            io::ErrorKind::Interrupted => Ok(false),
+            _ => Err(err),
+        };
+    }
+    Ok(pollfd.revents & libc::POLLIN != 0)
+}
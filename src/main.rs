@@ -1,29 +1,90 @@
 use chrono::offset::Local;
-use chrono::DateTime;
-use console::Color;
+use chrono::offset::LocalResult;
+use chrono::TimeZone;
 use console::Key;
-use fx::color;
+use fx::consts::APP_NAME;
 use fx::consts::MARGIN;
+use fx::consts::MIN_NAME_WIDTH;
 use fx::consts::PADDING;
+use fx::consts::POLL_INTERVAL;
 use fx::error::Error;
 use fx::expand_tilde;
+use fx::jobs::JobKind;
+use fx::jobs::JobResult;
+use fx::jobs::JobStatus;
+use fx::journal::Operation;
+use fx::keys::Action;
+use fx::keys::Binding;
+use fx::keys::Context;
+use fx::layout;
+use fx::layout::Align;
+use fx::opener;
+use fx::opener::Opener;
+use fx::ops;
 use fx::pad;
+use fx::poll_input;
+use fx::shell;
+use fx::sizes::format_size;
+use fx::sizes::SizeFormat;
+use fx::sort::sort_entries;
+use fx::text;
+use fx::theme;
+use fx::trash;
+use fx::users;
+use fx::Choices;
+use fx::Clipboard;
 use fx::Column;
 use fx::Config;
 use fx::Entry;
 use fx::EntryKind;
 use fx::FolderDir;
+use fx::Journal;
+use fx::KeyMode;
 use fx::Message;
 use fx::Mode;
 use fx::Move;
+use fx::Resolution;
 use fx::Result;
+use fx::Sort;
 use fx::State;
 use regex::Regex;
 use std::env;
+use std::ffi::OsStr;
+use std::ffi::OsString;
 use std::fs;
 use std::io;
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
+use std::path::PathBuf;
 use std::process::Command;
+use std::process::ExitStatus;
+use std::process::Stdio;
+use std::time::SystemTime;
+
+// The width of the original path column in the trash view
+const TRASH_PATH_WIDTH: usize = 50;
+// The choices of the conflict dialog and their names in the hint
+const DIALOG_CHOICES: [(Action, &str); 9] = [
+    (Action::Overwrite, "overwrite"),
+    (Action::Skip, "skip"),
+    (Action::Rename, "rename"),
+    (Action::Newer, "newer"),
+    (Action::OverwriteAll, "overwrite all"),
+    (Action::SkipAll, "skip all"),
+    (Action::RenameAll, "rename all"),
+    (Action::NewerAll, "newer all"),
+    (Action::Escape, "abort"),
+];
+// The choices of the open with menu and their names in the hint
+const MENU_CHOICES: [(Action, &str); 3] = [
+    (Action::Confirm, "open"),
+    (Action::Remember, "remember"),
+    (Action::Escape, "abort"),
+];
+
+// The names of the current entry and the selected entries
+type Focus = (Option<OsString>, Vec<OsString>);
 
 fn main() {
     match init() {
@@ -46,7 +107,7 @@ fn create_state(config: Config) -> Result<State> {
         Some(dir) => dir,
         None => "./".into(),
     };
-    let path = match fs::canonicalize(&Path::new(&current_dir)) {
+    let path = match fs::canonicalize(Path::new(&current_dir)) {
         Ok(path) => path,
         Err(_) => {
             return Err(Error::new(&format!(
@@ -55,7 +116,12 @@ fn create_state(config: Config) -> Result<State> {
             )))
         }
     };
-    Ok(State::new(config, path))
+    let journal = Journal::acquire()?;
+    let choices = Choices::acquire()?;
+    let mut state = State::new(config, path);
+    state.journal = journal;
+    state.choices = choices;
+    Ok(state)
 }
 
 // Initializes the user interface
@@ -72,46 +138,162 @@ fn init_ui(mut state: State) -> Result<()> {
 
 fn update_loop(state: &mut State) -> Result<()> {
     loop {
-        let key = state.term.read_key()?;
-        match key {
-            Key::Char('q') => {
+        let (action, count) = match read_action(state)? {
+            Some(action) => action,
+            None => continue,
+        };
+        let (action, count) = match action {
+            Action::Repeat => match state.repeat {
+                // a new count replaces the count of the repeated action
+                Some((action, repeat_count)) => (action, count.or(repeat_count)),
+                None => continue,
+            },
+            _ => (action, count),
+        };
+        if action == Action::Quit {
+            if !state.jobs.is_busy() {
                 break;
             }
-            Key::Char('j') => move_caret(state, Move::Down)?,
-            Key::Char('k') => move_caret(state, Move::Up)?,
-            Key::Char('h') => change_dir(state, FolderDir::Parent)?,
-            Key::Char('l') => change_dir(state, FolderDir::Child)?,
-            Key::Char('.') => toggle_dotfiles(state)?,
-            Key::Char('x') => toggle_select(state)?,
-            Key::Char('%') => select_all(state)?,
-            Key::Char('n') => move_caret(state, Move::Next)?,
-            Key::Char('N') => move_caret(state, Move::Prev)?,
-            Key::Char('g') => {
-                let key = state.term.read_key()?;
-                match key {
-                    Key::Char('g') => move_caret(state, Move::Top)?,
-                    Key::Char('e') => move_caret(state, Move::Bottom)?,
-                    _ => (),
-                }
+            state.set_message(Message::warn("Jobs are still running!"));
+            print(state)?;
+            continue;
+        }
+        if action != Action::Escape {
+            state.repeat = Some((action, count));
+        }
+        run_action(state, action, count)?;
+    }
+    Ok(())
+}
+
+// Performs the action, where the count repeats it or moves the caret by
+// the count, if the action supports it
+fn run_action(state: &mut State, action: Action, count: Option<usize>) -> Result<()> {
+    match action {
+        Action::Down => move_caret_by(state, Move::Down, count)?,
+        Action::Up => move_caret_by(state, Move::Up, count)?,
+        Action::Parent => {
+            for _ in 0..count.unwrap_or(1) {
+                change_dir(state, FolderDir::Parent)?;
             }
-            Key::Char('~') => change_dir(state, FolderDir::Home)?,
-            Key::Char('t') => prompt(state, "goto", &do_goto)?,
-            Key::Char('/') => prompt(state, "search", &do_search)?,
-            Key::Char('r') => {
-                read_dir(state)?;
-                print(state)?;
+        }
+        Action::Child => change_dir(state, FolderDir::Child)?,
+        Action::ToggleDotfiles => toggle_dotfiles(state)?,
+        Action::ToggleSelect => {
+            for _ in 0..count.unwrap_or(1) {
+                toggle_select(state)?;
             }
-            Key::Enter => open_file(state)?,
-            Key::Char('X') => {
-                state.selected.clear();
-                print(state)?;
+        }
+        Action::SelectAll => select_all(state)?,
+        Action::NextSelected => move_caret_by(state, Move::Next, count)?,
+        Action::PrevSelected => move_caret_by(state, Move::Prev, count)?,
+        Action::Top => move_caret_by(state, Move::Top, count)?,
+        Action::Bottom => move_caret_by(state, Move::Bottom, count)?,
+        Action::Home => change_dir(state, FolderDir::Home)?,
+        Action::Goto => prompt(state, "goto", &do_goto)?,
+        Action::Search => prompt(state, "search", &do_search)?,
+        Action::CycleSort => {
+            state.sort = state.sort.next();
+            change_sort(state)?;
+        }
+        Action::ReverseSort => {
+            state.reverse = !state.reverse;
+            change_sort(state)?;
+        }
+        Action::ToggleIgnoreCase => {
+            state.ignore_case = !state.ignore_case;
+            change_sort(state)?;
+        }
+        Action::DirSizes => compute_sizes(state)?,
+        Action::Reload => {
+            state.sizes.refresh();
+            read_dir(state)?;
+            print(state)?;
+        }
+        Action::Open => open_file(state)?,
+        Action::OpenWith => open_with(state)?,
+        Action::ClearSelection => {
+            state.selected.clear();
+            print(state)?;
+        }
+        Action::Yank => yank(state, false)?,
+        Action::Cut => yank(state, true)?,
+        Action::Paste => paste(state)?,
+        Action::Rename => {
+            if let Some(entry) = state.get_current().cloned() {
+                let file_name = entry.file_name.clone();
+                prompt_with_input(state, "rename", &file_name, &|state| {
+                    do_rename(state, &entry)
+                })?;
             }
-            _ => (),
         }
+        Action::BulkRename => bulk_rename(state)?,
+        Action::NewFile => prompt(state, "new file", &do_create_file)?,
+        Action::NewDir => prompt(state, "new dir", &do_create_dir)?,
+        Action::Symlink => {
+            if let Some(entry) = state.get_current().cloned() {
+                prompt(state, "symlink", &|state| do_symlink(state, &entry))?;
+            }
+        }
+        Action::Delete => trash_entries(state)?,
+        Action::TrashView => trash_view(state)?,
+        Action::Undo => {
+            for _ in 0..count.unwrap_or(1) {
+                undo(state, false)?;
+            }
+        }
+        Action::Redo => {
+            for _ in 0..count.unwrap_or(1) {
+                undo(state, true)?;
+            }
+        }
+        Action::CancelJob => cancel_job(state)?,
+        Action::Command => {
+            let focus = get_focus(state);
+            prompt(state, "command", &|state| do_command(state, &focus))?;
+        }
+        Action::Shell => {
+            let focus = get_focus(state);
+            prompt(state, "shell", &|state| do_shell(state, &focus))?;
+        }
+        _ => (),
     }
     Ok(())
 }
 
+// Reads an optional count and keys until they form a bound key sequence,
+// or none if the keys are not bound
+fn read_action(state: &mut State) -> Result<Option<(Action, Option<usize>)>> {
+    let mut count: Option<usize> = None;
+    let mut keys = Vec::new();
+    loop {
+        let key = read_key(state)?;
+        if let Key::Char(char) = key {
+            let digit = char.to_digit(10).map(|digit| digit as usize);
+            // a leading zero and bound digits are no count
+            let unbound = matches!(
+                state
+                    .keymap
+                    .lookup(Context::Normal, std::slice::from_ref(&key)),
+                Binding::Unbound
+            );
+            match digit {
+                Some(digit) if keys.is_empty() && (count.is_some() || (digit > 0 && unbound)) => {
+                    count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                    continue;
+                }
+                _ => (),
+            }
+        }
+        keys.push(key);
+        match state.keymap.lookup(Context::Normal, &keys) {
+            Binding::Action(action) => return Ok(Some((action, count))),
+            Binding::Prefix => continue,
+            Binding::Unbound => return Ok(None),
+        }
+    }
+}
+
 fn do_search(state: &mut State) -> Result<()> {
     let mut input = state.input.clone().unwrap_or_default();
     if input.is_empty() {
@@ -168,8 +350,181 @@ fn do_goto(state: &mut State) -> Result<()> {
     Ok(())
 }
 
+fn do_purge(state: &mut State) -> Result<()> {
+    let input = state.input.clone().unwrap_or_default();
+    if input != "y" {
+        return Ok(());
+    }
+    let items = state.trash.take().unwrap_or_default();
+    let mut count = 0;
+    let mut failed = None;
+    for i in state.get_target_indices() {
+        match trash::purge(&items[i]) {
+            Ok(_) => count += 1,
+            Err(err) => failed = Some(err),
+        }
+    }
+    read_trash(state)?;
+    match failed {
+        Some(err) => state.set_message(Message::error(&err.message)),
+        None => state.set_message(Message::info(&format!("{} purged", entries(count)))),
+    }
+    Ok(())
+}
+
+fn do_rename(state: &mut State, entry: &Entry) -> Result<()> {
+    let input = state.input.clone().unwrap_or_default();
+    if input.is_empty() {
+        return Ok(());
+    }
+    // the prompt shows the escaped name, which only is a new name if it was edited
+    if entry.file_name == input {
+        return Ok(());
+    }
+    let operation = Operation::Rename {
+        dir: state.path.clone(),
+        names: vec![(entry.os_name.clone(), input.clone().into())],
+    };
+    perform(state, "renamed", vec![operation])?;
+    focus_entry(state, input.as_ref());
+    Ok(())
+}
+
+fn do_create_file(state: &mut State) -> Result<()> {
+    let input = state.input.clone().unwrap_or_default();
+    if input.is_empty() {
+        return Ok(());
+    }
+    let operation = Operation::CreateFile {
+        path: state.path.join(&input),
+    };
+    create(state, operation, &input)
+}
+
+fn do_create_dir(state: &mut State) -> Result<()> {
+    let input = state.input.clone().unwrap_or_default();
+    if input.is_empty() {
+        return Ok(());
+    }
+    let input = input.trim_matches('/');
+    if let Err(err) = ops::validate_path(input) {
+        state.set_message(Message::error(&err.message));
+        return Ok(());
+    }
+    // only the first component of a nested path is listed in the current directory
+    let file_name = input.split('/').next().unwrap_or_default().to_string();
+    let operation = Operation::CreateDir {
+        path: state.path.join(input),
+        root: state.path.join(&file_name),
+    };
+    create(state, operation, &file_name)
+}
+
+fn do_symlink(state: &mut State, entry: &Entry) -> Result<()> {
+    let input = state.input.clone().unwrap_or_default();
+    if input.is_empty() {
+        return Ok(());
+    }
+    let operation = Operation::CreateSymlink {
+        target: entry.get_path(&state.path),
+        path: state.path.join(&input),
+    };
+    create(state, operation, &input)
+}
+
+fn create(state: &mut State, operation: Operation, file_name: &str) -> Result<()> {
+    if let Err(err) = ops::validate_name(file_name) {
+        state.set_message(Message::error(&err.message));
+        return Ok(());
+    }
+    perform(state, "created", vec![operation])?;
+    focus_entry(state, file_name.as_ref());
+    Ok(())
+}
+
+// Runs the command and reports its output in the message
+fn do_command(state: &mut State, focus: &Focus) -> Result<()> {
+    let mut command = match shell_command(state, focus) {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+    match command.stdin(Stdio::null()).output() {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let last_line = |output: &str| {
+                output
+                    .lines()
+                    .rev()
+                    .find(|line| !line.is_empty())
+                    .map(|line| line.to_string())
+            };
+            state.sizes.invalidate(std::slice::from_ref(&state.path));
+            reload_dir(state)?;
+            match output.status.success() {
+                true => state.set_message(Message::info(
+                    &last_line(&stdout).unwrap_or_else(|| "Command finished".into()),
+                )),
+                false => state.set_message(Message::error(
+                    &last_line(&stderr)
+                        .unwrap_or_else(|| format!("Command failed! ({})", output.status)),
+                )),
+            }
+        }
+        Err(_) => state.set_message(Message::error("Unable to run command!")),
+    }
+    Ok(())
+}
+
+// Runs the command in the terminal and waits for a key afterwards
+fn do_shell(state: &mut State, focus: &Focus) -> Result<()> {
+    let mut command = match shell_command(state, focus) {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+    match run_in_terminal(state, &mut command) {
+        Ok(status) => {
+            state.term.write_line("\nPress any key to continue...")?;
+            state.term.read_key()?;
+            state.sizes.invalidate(std::slice::from_ref(&state.path));
+            reload_dir(state)?;
+            if !status.success() {
+                state.set_message(Message::error(&format!("Command failed! ({})", status)));
+            }
+        }
+        Err(_) => state.set_message(Message::error("Unable to run command!")),
+    }
+    Ok(())
+}
+
+// Builds a shell command from the input with the placeholders expanded
+// for the entries focused when the prompt was opened
+fn shell_command(state: &State, focus: &Focus) -> Option<Command> {
+    let input = state.input.clone().unwrap_or_default();
+    if input.trim().is_empty() {
+        return None;
+    }
+    let (current, selected) = focus;
+    let selected = match selected.is_empty() {
+        true => current.iter().cloned().collect(),
+        false => selected.clone(),
+    };
+    let expanded = shell::expand(&input, current.as_deref(), &selected, &state.path);
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(expanded).current_dir(&state.path);
+    Some(command)
+}
+
 fn prompt(state: &mut State, title: &str, f: &dyn Fn(&mut State) -> Result<()>) -> Result<()> {
-    let shift = 3 + title.len() + 1;
+    prompt_with_input(state, title, "", f)
+}
+
+fn prompt_with_input(
+    state: &mut State,
+    title: &str,
+    input: &str,
+    f: &dyn Fn(&mut State) -> Result<()>,
+) -> Result<()> {
     let key = title.to_string();
     if !state.history.contains_key(&key) {
         state.history.insert(key.clone(), Vec::new());
@@ -178,96 +533,110 @@ fn prompt(state: &mut State, title: &str, f: &dyn Fn(&mut State) -> Result<()>)
     let history = histories.get_mut(&key).unwrap();
     state.title = Some(title.into());
     state.mode = Mode::Prompt;
-    state.input = None;
-    state.cursor = 0;
+    state.input = match input.is_empty() {
+        true => None,
+        false => Some(input.to_string()),
+    };
+    state.cursor = input.len();
     state.history_index = 0;
     print(state)?;
-    state.term.move_cursor_to(shift, 1)?;
+    state.term.move_cursor_to(get_cursor_column(state), 1)?;
     state.term.show_cursor()?;
     loop {
-        let key = state.term.read_key()?;
-        match key {
-            Key::Escape => {
+        let key = read_key(state)?;
+        // unbound characters are typed into the input
+        match (state.keymap.get_action(Context::Prompt, &key), key) {
+            (Some(Action::Escape), _) => {
                 state.mode = Mode::Normal;
                 state.term.hide_cursor()?;
                 print(state)?;
                 break;
             }
-            Key::Backspace => {
+            (Some(Action::DeleteBack), _) => {
                 let mut search = state.input.clone().unwrap_or_default();
                 if !search.is_empty() && state.cursor > 0 {
-                    state.cursor -= 1;
-                    search.remove(state.cursor);
+                    let start = text::prev_boundary(&search, state.cursor);
+                    search.replace_range(start..state.cursor, "");
+                    state.cursor = start;
                     state.input = Some(search);
                     state.term.hide_cursor()?;
                     print(state)?;
-                    state.term.move_cursor_to(shift + state.cursor, 1)?;
+                    state.term.move_cursor_to(get_cursor_column(state), 1)?;
                     state.term.show_cursor()?;
                 }
             }
-            Key::Del => {
+            (Some(Action::DeleteForward), _) => {
                 let mut search = state.input.clone().unwrap_or_default();
                 if state.cursor < search.len() {
-                    search.remove(state.cursor);
+                    let end = text::next_boundary(&search, state.cursor);
+                    search.replace_range(state.cursor..end, "");
                     state.input = Some(search);
                     state.term.hide_cursor()?;
                     print(state)?;
-                    state.term.move_cursor_to(shift + state.cursor, 1)?;
+                    state.term.move_cursor_to(get_cursor_column(state), 1)?;
                     state.term.show_cursor()?;
                 }
             }
-            Key::Char(char) => {
+            (None, Key::Char(char)) => {
                 let mut search = state.input.clone().unwrap_or_default();
                 search.insert(state.cursor, char);
-                state.cursor += 1;
+                state.cursor += char.len_utf8();
                 state.input = Some(search);
                 state.term.hide_cursor()?;
                 print(state)?;
-                state.term.move_cursor_to(shift + state.cursor, 1)?;
+                state.term.move_cursor_to(get_cursor_column(state), 1)?;
                 state.term.show_cursor()?;
             }
-            Key::ArrowLeft => {
-                if state.cursor > 0 {
-                    state.cursor -= 1;
-                    state.term.move_cursor_to(shift + state.cursor, 1)?;
-                    state.term.show_cursor()?;
-                }
+            (Some(Action::CursorLeft), _) if state.cursor > 0 => {
+                state.cursor =
+                    text::prev_boundary(&state.input.clone().unwrap_or_default(), state.cursor);
+                state.term.move_cursor_to(get_cursor_column(state), 1)?;
+                state.term.show_cursor()?;
             }
-            Key::ArrowRight => {
-                if state.cursor < state.input.clone().unwrap_or_default().len() {
-                    state.cursor += 1;
-                    state.term.move_cursor_to(shift + state.cursor, 1)?;
-                    state.term.show_cursor()?;
-                }
+            (Some(Action::CursorRight), _)
+                if state.cursor < state.input.clone().unwrap_or_default().len() =>
+            {
+                state.cursor =
+                    text::next_boundary(&state.input.clone().unwrap_or_default(), state.cursor);
+                state.term.move_cursor_to(get_cursor_column(state), 1)?;
+                state.term.show_cursor()?;
             }
-            Key::ArrowUp => {
-                if !history.is_empty() && state.history_index < history.len() {
-                    state.history_index += 1;
+            (Some(Action::HistoryPrev), _)
+                if !history.is_empty() && state.history_index < history.len() =>
+            {
+                state.history_index += 1;
+                state.input = Some(history[history.len() - state.history_index].clone());
+                state.cursor = state.input.clone().unwrap_or_default().len();
+                state.term.hide_cursor()?;
+                print(state)?;
+                state.term.move_cursor_to(get_cursor_column(state), 1)?;
+                state.term.show_cursor()?;
+            }
+            (Some(Action::HistoryNext), _) if !history.is_empty() => {
+                if state.history_index > 1 {
+                    state.history_index -= 1;
                     state.input = Some(history[history.len() - state.history_index].clone());
-                    state.cursor = state.input.clone().unwrap_or_default().len();
-                    state.term.hide_cursor()?;
-                    print(state)?;
-                    state.term.move_cursor_to(shift + state.cursor, 1)?;
-                    state.term.show_cursor()?;
+                } else {
+                    state.history_index = 0;
+                    state.input = None;
                 }
+                state.cursor = state.input.clone().unwrap_or_default().len();
+                state.term.hide_cursor()?;
+                print(state)?;
+                state.term.move_cursor_to(get_cursor_column(state), 1)?;
+                state.term.show_cursor()?;
             }
-            Key::ArrowDown => {
-                if !history.is_empty() {
-                    if state.history_index > 1 {
-                        state.history_index -= 1;
-                        state.input = Some(history[history.len() - state.history_index].clone());
-                    } else {
-                        state.history_index = 0;
-                        state.input = None;
-                    }
-                    state.cursor = state.input.clone().unwrap_or_default().len();
-                    state.term.hide_cursor()?;
-                    print(state)?;
-                    state.term.move_cursor_to(shift + state.cursor, 1)?;
-                    state.term.show_cursor()?;
-                }
+            (Some(Action::CursorStart), _) => {
+                state.cursor = 0;
+                state.term.move_cursor_to(get_cursor_column(state), 1)?;
+                state.term.show_cursor()?;
+            }
+            (Some(Action::CursorEnd), _) => {
+                state.cursor = state.input.clone().unwrap_or_default().len();
+                state.term.move_cursor_to(get_cursor_column(state), 1)?;
+                state.term.show_cursor()?;
             }
-            Key::Enter => {
+            (Some(Action::Confirm), _) => {
                 if let Some(input) = state.input.clone() {
                     history.push(input);
                 }
@@ -407,6 +776,30 @@ fn move_caret(state: &mut State, movement: Move) -> Result<()> {
     Ok(())
 }
 
+// Moves the caret count times, or to the entry numbered by the count for
+// the top and bottom movements
+fn move_caret_by(state: &mut State, movement: Move, count: Option<usize>) -> Result<()> {
+    let count = match count {
+        Some(count) if !state.list.is_empty() => count,
+        _ => return move_caret(state, movement),
+    };
+    let last = state.list.len() - 1;
+    let index = match movement {
+        Move::Down => state.index.saturating_add(count).min(last),
+        Move::Up => state.index.saturating_sub(count),
+        Move::Top | Move::Bottom => count.saturating_sub(1).min(last),
+        _ => {
+            for _ in 0..count {
+                move_caret(state, movement)?;
+            }
+            return Ok(());
+        }
+    };
+    focus_index(state, index);
+    print(state)?;
+    Ok(())
+}
+
 fn change_dir(state: &mut State, dir: FolderDir) -> Result<()> {
     match dir {
         FolderDir::Parent => {
@@ -429,7 +822,7 @@ fn change_dir(state: &mut State, dir: FolderDir) -> Result<()> {
                 open_file(state)?;
                 return Ok(());
             }
-            state.path.push(entry.file_name.clone());
+            state.path.push(entry.os_name.clone());
             state.index = 0;
             state.offset = 0;
             state.selected.clear();
@@ -452,9 +845,60 @@ fn change_dir(state: &mut State, dir: FolderDir) -> Result<()> {
     Ok(())
 }
 
+// Opens the selected files or the current file, passing files with the same app together
 fn open_file(state: &mut State) -> Result<()> {
+    let indices = match state.selected.is_empty() {
+        true => state.get_target_indices(),
+        false => state
+            .get_target_indices()
+            .into_iter()
+            .filter(|i| !state.list[*i].is_dir())
+            .collect(),
+    };
+    let entry = match indices.first() {
+        Some(i) => &state.list[*i],
+        None => return Ok(()),
+    };
+    if entry.is_dir() {
+        state.set_message(Message::warn("Entry is not a file!"));
+        print(state)?;
+        return Ok(());
+    }
+    let mut groups: Vec<(Opener, Vec<PathBuf>)> = Vec::new();
+    let mut missing = 0;
+    for i in indices {
+        let entry = &state.list[i];
+        let app = match state.choices.get_app(entry) {
+            Some(opener) => Some(opener),
+            None => state.config.get_app(entry, &state.path),
+        };
+        let opener = match app {
+            Some(opener) => opener,
+            None => {
+                missing += 1;
+                continue;
+            }
+        };
+        let path = entry.get_path(&state.path);
+        match groups.iter_mut().find(|(other, _)| other.app == opener.app) {
+            Some((_, files)) => files.push(path),
+            None => groups.push((opener, vec![path])),
+        }
+    }
+    if missing > 0 {
+        state.set_message(Message::warn("No app for given file type specified!"));
+    }
+    for (opener, files) in groups {
+        run_opener(state, &opener, &files);
+    }
+    print(state)?;
+    Ok(())
+}
+
+// Opens the current file with an app chosen from all matching apps
+fn open_with(state: &mut State) -> Result<()> {
     let entry = match state.get_current() {
-        Some(entry) => entry,
+        Some(entry) => entry.clone(),
         None => return Ok(()),
     };
     if entry.is_dir() {
@@ -462,27 +906,587 @@ fn open_file(state: &mut State) -> Result<()> {
         print(state)?;
         return Ok(());
     }
-    let file_ext = entry.file_name.split('.').last().unwrap_or_default();
-    let app = match state.config.get_app(file_ext) {
-        Some(app) => app,
+    let apps = state.config.get_apps(&entry, &state.path);
+    if apps.is_empty() {
+        state.set_message(Message::warn("No app for given file type specified!"));
+        print(state)?;
+        return Ok(());
+    }
+    let items = apps.iter().map(|opener| opener.app.clone()).collect();
+    let (index, remember) = match choose(state, "open with", items)? {
+        Some(choice) => choice,
         None => {
-            state.set_message(Message::warn("No app for given file extension specified!"));
             print(state)?;
             return Ok(());
         }
     };
-    let status = Command::new("bash")
-        .args(&["-c", &format!("{} '{}'", app, &entry.file_name)])
-        .current_dir(&state.path)
-        .status()
-        .unwrap();
-    if !status.success() {
-        state.message = Some(Message::error("Unable to open file!"));
+    let opener = &apps[index];
+    if remember {
+        match state.choices.remember(&entry, opener) {
+            Ok(_) => state.set_message(Message::info(&format!(
+                "Remembered '{}' for '{}'",
+                opener.app,
+                entry.get_extension()
+            ))),
+            Err(err) => state.set_message(Message::error(&err.message)),
+        }
+    }
+    run_opener(state, opener, &[entry.get_path(&state.path)]);
+    print(state)?;
+    Ok(())
+}
+
+// Asks which menu item to use and if the choice should be remembered,
+// or none if the user aborted
+fn choose(state: &mut State, title: &str, items: Vec<String>) -> Result<Option<(usize, bool)>> {
+    let length = items.len();
+    state.title = Some(title.into());
+    state.menu = Some(items);
+    state.menu_index = 0;
+    state.mode = Mode::Menu;
+    print(state)?;
+    let choice = loop {
+        let key = read_key(state)?;
+        match state.keymap.get_action(Context::Menu, &key) {
+            Some(Action::Escape) | Some(Action::Quit) => break None,
+            Some(Action::Confirm) => break Some((state.menu_index, false)),
+            Some(Action::Remember) => break Some((state.menu_index, true)),
+            Some(Action::Down) if state.menu_index + 1 < length => {
+                state.menu_index += 1;
+                print(state)?;
+            }
+            Some(Action::Up) if state.menu_index > 0 => {
+                state.menu_index -= 1;
+                print(state)?;
+            }
+            _ => (),
+        }
+    };
+    state.mode = Mode::Normal;
+    state.title = None;
+    state.menu = None;
+    Ok(choice)
+}
+
+// Runs the app with the files and reports failures as message
+fn run_opener(state: &mut State, opener: &Opener, files: &[PathBuf]) {
+    let commands = match opener.build_all(files) {
+        Ok(commands) => commands,
+        Err(err) => {
+            state.set_message(Message::error(&err.message));
+            return;
+        }
+    };
+    for mut command in commands {
+        command.current_dir(&state.path);
+        let result = match opener.gui {
+            true => opener::spawn_detached(&mut command)
+                .map(|_| true)
+                .map_err(Error::from),
+            false => run_in_terminal(state, &mut command).map(|status| status.success()),
+        };
+        match result {
+            Ok(true) => (),
+            Ok(false) => state.set_message(Message::error("Unable to open file!")),
+            Err(err) => state.set_message(Message::error(&format!(
+                "Unable to run '{}'! Reason: {}",
+                opener.app, err
+            ))),
+        }
+    }
+}
+
+// Puts the selected entries or the current entry into the clipboard
+fn yank(state: &mut State, cut: bool) -> Result<()> {
+    let targets = state.get_targets();
+    if targets.is_empty() {
+        return Ok(());
+    }
+    let count = targets.len();
+    if cut {
+        state.clipboard = Some(Clipboard::Cut(targets));
+        state.set_message(Message::info(&format!("{} cut", entries(count))));
+    } else {
+        state.clipboard = Some(Clipboard::Copy(targets));
+        state.set_message(Message::info(&format!("{} yanked", entries(count))));
+    }
+    state.selected.clear();
+    print(state)?;
+    Ok(())
+}
+
+// Moves the selected entries or the current entry into the trash
+fn trash_entries(state: &mut State) -> Result<()> {
+    let targets = state.get_targets();
+    if targets.is_empty() {
+        return Ok(());
+    }
+    let operations = targets
+        .into_iter()
+        .map(|path| Operation::Trash {
+            path,
+            name: String::new(),
+        })
+        .collect();
+    // entries on other filesystems are copied into the trash
+    state.message = None;
+    state
+        .jobs
+        .push(state.path.clone(), JobKind::Perform, "trashed", operations);
+    print(state)?;
+    Ok(())
+}
+
+// Shows the trashed entries until the trash view is closed
+fn trash_view(state: &mut State) -> Result<()> {
+    let index = state.index;
+    let offset = state.offset;
+    state.index = 0;
+    state.offset = 0;
+    state.message = None;
+    read_trash(state)?;
+    print(state)?;
+    loop {
+        let (action, count) = match read_action(state)? {
+            Some(action) => action,
+            None => continue,
+        };
+        match action {
+            Action::Quit | Action::Escape | Action::TrashView => break,
+            Action::Down => move_caret_by(state, Move::Down, count)?,
+            Action::Up => move_caret_by(state, Move::Up, count)?,
+            Action::ToggleSelect => {
+                for _ in 0..count.unwrap_or(1) {
+                    toggle_select(state)?;
+                }
+            }
+            Action::SelectAll => select_all(state)?,
+            Action::ClearSelection => {
+                state.selected.clear();
+                print(state)?;
+            }
+            Action::Top => move_caret_by(state, Move::Top, count)?,
+            Action::Bottom => move_caret_by(state, Move::Bottom, count)?,
+            Action::Restore => restore_trash(state)?,
+            Action::Delete if !state.list.is_empty() => prompt(state, "purge? (y/n)", &do_purge)?,
+            _ => (),
+        }
+    }
+    // the trash items are no entries of the directory to keep the focus on
+    state.trash = None;
+    state.list.clear();
+    state.selected.clear();
+    state.index = index;
+    state.offset = offset;
+    state.message = None;
+    reload_dir(state)?;
+    print(state)?;
+    Ok(())
+}
+
+// Restores the selected trash items or the current trash item
+fn restore_trash(state: &mut State) -> Result<()> {
+    let items = state.trash.take().unwrap_or_default();
+    let mut count = 0;
+    let mut failed = None;
+    for i in state.get_target_indices() {
+        match trash::restore(&items[i]) {
+            Ok(_) => count += 1,
+            Err(err) => failed = Some(err),
+        }
+        state.sizes.invalidate(std::slice::from_ref(&items[i].path));
+    }
+    read_trash(state)?;
+    match failed {
+        Some(err) => state.set_message(Message::error(&err.message)),
+        None => state.set_message(Message::info(&format!("{} restored", entries(count)))),
     }
     print(state)?;
     Ok(())
 }
 
+// Reads the trash items into the list
+fn read_trash(state: &mut State) -> Result<()> {
+    let items = trash::list()?;
+    let mut list = Vec::new();
+    for item in &items {
+        let entry = match Entry::read(item.name.clone().into(), &item.get_file()?) {
+            Ok(entry) => entry,
+            Err(_) => Entry {
+                file_name: item.name.clone(),
+                os_name: item.name.clone().into(),
+                ..Entry::default()
+            },
+        };
+        list.push(entry);
+    }
+    state.list = list;
+    state.trash = Some(items);
+    state.selected.clear();
+    if state.index >= state.list.len() {
+        state.index = state.list.len().saturating_sub(1);
+    }
+    if state.offset > state.index {
+        state.offset = state.index;
+    }
+    Ok(())
+}
+
+// Copies or moves the clipboard entries into the current directory
+fn paste(state: &mut State) -> Result<()> {
+    let (paths, cut) = match state.clipboard.take() {
+        Some(Clipboard::Copy(paths)) => (paths, false),
+        Some(Clipboard::Cut(paths)) => (paths, true),
+        None => {
+            state.set_message(Message::warn("Clipboard is empty!"));
+            print(state)?;
+            return Ok(());
+        }
+    };
+    let mut operations = Vec::new();
+    let mut conflict = None;
+    let mut apply_to_all = None;
+    for src in &paths {
+        let mut dest = match src.file_name() {
+            Some(file_name) => state.path.join(file_name),
+            None => continue,
+        };
+        if dest.symlink_metadata().is_ok() {
+            if src.starts_with(&dest) && src != &dest {
+                // overwriting would remove the source itself
+                conflict = Some(dest);
+                continue;
+            }
+            let resolution = match apply_to_all {
+                Some(resolution) => resolution,
+                None => match resolve_conflict(state, &dest)? {
+                    Some((resolution, all)) => {
+                        if all {
+                            apply_to_all = Some(resolution);
+                        }
+                        resolution
+                    }
+                    None => {
+                        state.clipboard = Some(match cut {
+                            true => Clipboard::Cut(paths),
+                            false => Clipboard::Copy(paths),
+                        });
+                        print(state)?;
+                        return Ok(());
+                    }
+                },
+            };
+            match resolution {
+                Resolution::Skip => continue,
+                Resolution::OverwriteIfNewer if !ops::is_newer(src, &dest) => continue,
+                // entries cannot replace themselves, so they are pasted as a copy
+                _ if src == &dest && cut => continue,
+                Resolution::Rename => dest = ops::unique_path(&dest),
+                _ if src == &dest => dest = ops::unique_path(&dest),
+                // replaced entries are trashed, so that they can be restored
+                _ => operations.push(Operation::Replace {
+                    path: dest.clone(),
+                    name: String::new(),
+                }),
+            }
+        }
+        let from = src.clone();
+        operations.push(match cut {
+            true => Operation::Move { from, to: dest },
+            false => Operation::Copy { from, to: dest },
+        });
+    }
+    if !cut {
+        // yanked entries can be pasted multiple times
+        state.clipboard = Some(Clipboard::Copy(paths));
+    }
+    state.message = None;
+    state
+        .jobs
+        .push(state.path.clone(), JobKind::Perform, "pasted", operations);
+    if let Some(dest) = conflict {
+        state.set_message(Message::error(&format!(
+            "Unable to replace '{}'!",
+            dest.file_name().unwrap_or_default().to_string_lossy()
+        )));
+    }
+    print(state)?;
+    Ok(())
+}
+
+// Asks how to resolve a conflict with an existing entry and if the
+// resolution applies to all conflicts, or none if the user aborted
+fn resolve_conflict(state: &mut State, dest: &Path) -> Result<Option<(Resolution, bool)>> {
+    state.title = Some(format!(
+        "'{}' already exists!",
+        dest.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    state.mode = Mode::Dialog;
+    print(state)?;
+    let resolution = loop {
+        let key = read_key(state)?;
+        let resolution = match state.keymap.get_action(Context::Dialog, &key) {
+            Some(Action::Escape) => break None,
+            Some(Action::Overwrite) => (Resolution::Overwrite, false),
+            Some(Action::OverwriteAll) => (Resolution::Overwrite, true),
+            Some(Action::Skip) => (Resolution::Skip, false),
+            Some(Action::SkipAll) => (Resolution::Skip, true),
+            Some(Action::Rename) => (Resolution::Rename, false),
+            Some(Action::RenameAll) => (Resolution::Rename, true),
+            Some(Action::Newer) => (Resolution::OverwriteIfNewer, false),
+            Some(Action::NewerAll) => (Resolution::OverwriteIfNewer, true),
+            _ => continue,
+        };
+        break Some(resolution);
+    };
+    state.mode = Mode::Normal;
+    state.title = None;
+    Ok(resolution)
+}
+
+// Renames the selected entries by editing their names in the editor
+fn bulk_rename(state: &mut State) -> Result<()> {
+    let targets = state.get_targets();
+    if targets.is_empty() {
+        return Ok(());
+    }
+    let os_names: Vec<OsString> = targets
+        .iter()
+        .map(|path| path.file_name().unwrap_or_default().to_os_string())
+        .collect();
+    let names: Vec<String> = os_names.iter().map(|name| text::escape(name)).collect();
+    let (file, mut handle) = match ops::create_temp(&format!("{}-rename", APP_NAME), ".txt") {
+        Ok(temp) => temp,
+        Err(err) => {
+            state.set_message(Message::error(&format!(
+                "Unable to create rename file! Reason: {}",
+                err
+            )));
+            print(state)?;
+            return Ok(());
+        }
+    };
+    let written = handle.write_all((names.join("\n") + "\n").as_bytes());
+    drop(handle);
+    if let Err(err) = written {
+        let _ = fs::remove_file(&file);
+        state.set_message(Message::error(&format!(
+            "Unable to write rename file! Reason: {}",
+            err
+        )));
+        print(state)?;
+        return Ok(());
+    }
+    let editor = env::var("EDITOR").unwrap_or_else(|_| "vi".into());
+    let mut args = editor.split_whitespace();
+    let mut command = Command::new(args.next().unwrap_or("vi"));
+    command.args(args).arg(&file).current_dir(&state.path);
+    let status = run_in_terminal(state, &mut command);
+    let edited = fs::read_to_string(&file);
+    // the editor may have removed or replaced the file
+    let _ = fs::remove_file(&file);
+    match status {
+        Ok(status) if status.success() => (),
+        _ => {
+            state.set_message(Message::error("Unable to run editor!"));
+            print(state)?;
+            return Ok(());
+        }
+    }
+    let edited: Vec<String> = match edited {
+        Ok(edited) => edited.lines().map(|line| line.to_string()).collect(),
+        Err(err) => {
+            state.set_message(Message::error(&format!(
+                "Unable to read rename file! Reason: {}",
+                err
+            )));
+            print(state)?;
+            return Ok(());
+        }
+    };
+    if edited.len() != names.len() {
+        state.set_message(Message::error("Number of names does not match!"));
+        print(state)?;
+        return Ok(());
+    }
+    // unchanged lines keep their raw name, even if it is not valid UTF-8
+    let names = os_names
+        .into_iter()
+        .zip(names)
+        .zip(edited)
+        .filter(|((_, name), edited)| name != edited)
+        .map(|((os_name, _), edited)| (os_name, edited.into()))
+        .collect();
+    let operation = Operation::Rename {
+        dir: state.path.clone(),
+        names,
+    };
+    perform(state, "renamed", vec![operation])?;
+    print(state)?;
+    Ok(())
+}
+
+// Performs the operations, records them in the journal and reports the result
+fn perform(state: &mut State, verb: &str, operations: Vec<Operation>) -> Result<()> {
+    let changed: Vec<PathBuf> = operations.iter().flat_map(Operation::get_paths).collect();
+    let mut done = Vec::new();
+    let mut failed = None;
+    for mut operation in operations {
+        match operation.apply() {
+            Ok(_) => done.push(operation),
+            Err(err) => failed = Some(err),
+        }
+    }
+    state.sizes.invalidate(&changed);
+    reload_dir(state)?;
+    record(state, verb, done, failed);
+    Ok(())
+}
+
+// Records the performed operations in the journal and reports the result
+fn record(state: &mut State, verb: &str, operations: Vec<Operation>, mut failed: Option<Error>) {
+    let count = operations.iter().map(|operation| operation.count()).sum();
+    let description = format!("{} {}", entries(count), verb);
+    if let Err(err) = state.journal.record(&description, operations) {
+        failed = Some(err);
+    }
+    match failed {
+        Some(err) => state.set_message(Message::error(&err.message)),
+        None => state.set_message(Message::info(&description)),
+    }
+}
+
+// Waits for the next key while updating the progress of running jobs
+fn read_key(state: &mut State) -> Result<Key> {
+    if reload_pending(state)? {
+        redraw(state)?;
+    }
+    let key_mode = KeyMode::enable();
+    while (state.jobs.is_busy() || state.sizes.is_busy()) && !poll_input(POLL_INTERVAL)? {
+        let mut changed = state.sizes.poll();
+        if changed && state.sort == Sort::Size && state.trash.is_none() {
+            resort(state);
+        }
+        if let Some(finished) = state.jobs.poll() {
+            for result in finished {
+                finish_job(state, result)?;
+            }
+            reload_pending(state)?;
+            changed = true;
+        }
+        if changed {
+            redraw(state)?;
+        }
+    }
+    drop(key_mode);
+    Ok(state.term.read_key()?)
+}
+
+fn finish_job(state: &mut State, result: JobResult) -> Result<()> {
+    let changed: Vec<PathBuf> = result
+        .operations
+        .iter()
+        .chain(&result.pending)
+        .flat_map(Operation::get_paths)
+        .collect();
+    state.sizes.invalidate(&changed);
+    if result.dir == state.path || changed.iter().any(|path| path.parent() == Some(&state.path))
+    {
+        state.reload = true;
+    }
+    match result.kind {
+        JobKind::Perform => record(state, &result.verb, result.operations, result.error),
+        JobKind::Undo | JobKind::Redo => {
+            let redo = result.kind == JobKind::Redo;
+            let completed = !result.operations.is_empty();
+            let mut failed = result.error;
+            if let Err(err) = state.journal.complete(
+                redo,
+                &result.verb,
+                result.operations,
+                result.pending,
+            ) {
+                failed = Some(err);
+            }
+            match failed {
+                Some(err) => state.set_message(Message::error(&err.message)),
+                None if completed => state.set_message(Message::info(&format!(
+                    "{}: {}",
+                    if redo { "Redone" } else { "Undone" },
+                    result.verb
+                ))),
+                None => (),
+            }
+        }
+    }
+    if result.cancelled {
+        state.set_message(Message::warn("Job cancelled!"));
+    }
+    Ok(())
+}
+
+// Reloads the directory changed by finished jobs once neither the trash nor
+// a prompt, menu or dialog refers to the listed entries, returns if it was reloaded
+fn reload_pending(state: &mut State) -> io::Result<bool> {
+    if !state.reload || state.mode != Mode::Normal || state.trash.is_some() {
+        return Ok(false);
+    }
+    reload_dir(state)?;
+    Ok(true)
+}
+
+fn cancel_job(state: &mut State) -> Result<()> {
+    match state.jobs.cancel() {
+        true => state.set_message(Message::warn("Cancelling job...")),
+        false => state.set_message(Message::warn("No job running!")),
+    }
+    print(state)?;
+    Ok(())
+}
+
+// Queues reverting the last recorded operations or performing them again,
+// since entries moved across filesystems are copied
+fn undo(state: &mut State, redo: bool) -> Result<()> {
+    match state.journal.pop(redo) {
+        Some(record) => {
+            let kind = match redo {
+                true => JobKind::Redo,
+                false => JobKind::Undo,
+            };
+            state.message = None;
+            state.jobs.push(
+                state.path.clone(),
+                kind,
+                &record.description,
+                record.operations,
+            );
+        }
+        None => state.set_message(Message::warn(&format!(
+            "Nothing to {}!",
+            if redo { "redo" } else { "undo" }
+        ))),
+    }
+    print(state)?;
+    Ok(())
+}
+
+// Hands the terminal over to the command until it exits
+fn run_in_terminal(state: &mut State, command: &mut Command) -> Result<ExitStatus> {
+    state.term.clear_last_lines(state.lines)?;
+    state.term.show_cursor()?;
+    let status = command.status();
+    state.term.hide_cursor()?;
+    state.lines = 0;
+    Ok(status?)
+}
+
+fn entries(count: usize) -> String {
+    match count {
+        1 => "1 entry".to_string(),
+        _ => format!("{} entries", count),
+    }
+}
+
 fn toggle_dotfiles(state: &mut State) -> Result<()> {
     state.show_dotfiles = !state.show_dotfiles;
     state.index = 0;
@@ -516,47 +1520,180 @@ fn select_all(state: &mut State) -> Result<()> {
     Ok(())
 }
 
+// Computes the recursive sizes of the selected directories or of all listed directories
+fn compute_sizes(state: &mut State) -> Result<()> {
+    let indices = match state.selected.is_empty() {
+        true => (0..state.list.len()).collect(),
+        false => state.get_target_indices(),
+    };
+    let mut count = 0;
+    for i in indices {
+        if state.list[i].is_dir() {
+            let path = state.list[i].get_path(&state.path);
+            state.sizes.request(path);
+            count += 1;
+        }
+    }
+    if count == 0 {
+        state.set_message(Message::warn("No directories to measure!"));
+    }
+    print(state)?;
+    Ok(())
+}
+
+// Sorts the file list by the changed sort mode and reports it
+fn change_sort(state: &mut State) -> Result<()> {
+    if state.trash.is_some() {
+        return Ok(());
+    }
+    resort(state);
+    let order = match state.reverse {
+        true => "reverse",
+        false => "ascending",
+    };
+    let case = match state.ignore_case {
+        true => "ignoring case",
+        false => "matching case",
+    };
+    state.set_message(Message::info(&format!(
+        "Sorted by {} in {} order, {}",
+        state.sort, order, case
+    )));
+    print(state)?;
+    Ok(())
+}
+
+// Sorts the file list again, keeping the caret and the selection on their entries
+fn resort(state: &mut State) {
+    let focus = get_focus(state);
+    sort_list(state);
+    set_focus(state, focus);
+}
+
+// Get names of the current entry and the selected entries, which stay
+// valid when the list changes
+fn get_focus(state: &State) -> Focus {
+    let current = state.get_current().map(|entry| entry.os_name.clone());
+    let selected = match state.selected.is_empty() {
+        true => Vec::new(),
+        false => state
+            .get_target_indices()
+            .iter()
+            .map(|i| state.list[*i].os_name.clone())
+            .collect(),
+    };
+    (current, selected)
+}
+
+// Moves the caret and the selection back to the entries with the names,
+// where missing entries are left out
+fn set_focus(state: &mut State, (current, selected): Focus) {
+    state.selected = state
+        .list
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| selected.contains(&entry.os_name))
+        .map(|(i, _)| i)
+        .collect();
+    if let Some(current) = current {
+        focus_entry(state, &current);
+    }
+}
+
+// Sorts the file list, where directories are sorted by their computed size
+fn sort_list(state: &mut State) {
+    let dir = &state.path;
+    let sizes = &state.sizes;
+    let get_size = |entry: &Entry| match entry.is_dir() {
+        true => sizes.get(&entry.get_path(dir)).unwrap_or_default(),
+        false => entry.size as u64,
+    };
+    sort_entries(
+        &mut state.list,
+        state.sort,
+        state.reverse,
+        state.ignore_case,
+        &get_size,
+    );
+}
+
+// Reads the current directory again, keeping the caret and the selection
+// on their entries and the caret within the list
+fn reload_dir(state: &mut State) -> io::Result<()> {
+    let focus = get_focus(state);
+    state.reload = false;
+    read_dir(state)?;
+    set_focus(state, focus);
+    if state.index >= state.list.len() {
+        state.index = state.list.len().saturating_sub(1);
+    }
+    if state.offset > state.index {
+        state.offset = state.index;
+    }
+    Ok(())
+}
+
+// Moves the caret to the entry with the given name
+fn focus_entry(state: &mut State, file_name: &OsStr) {
+    let index = match state
+        .list
+        .iter()
+        .position(|entry| entry.os_name == file_name)
+    {
+        Some(index) => index,
+        None => return,
+    };
+    focus_index(state, index);
+}
+
+// Moves the caret to the index and scrolls the list to keep it visible
+fn focus_index(state: &mut State, index: usize) {
+    let rows = (state.lines + 1).saturating_sub(MARGIN);
+    state.index = index;
+    if index < state.offset + PADDING {
+        state.offset = index.saturating_sub(PADDING);
+    } else if index + PADDING >= state.offset + rows {
+        state.offset = (index + PADDING + 1).saturating_sub(rows);
+    }
+}
+
 // Reads the current directory
 fn read_dir(state: &mut State) -> io::Result<()> {
-    let mut dirs = Vec::new();
-    let mut symlinks = Vec::new();
-    let mut files = Vec::new();
+    let mut list = Vec::new();
     for dir_entry in fs::read_dir(&state.path)? {
         let item = dir_entry?;
-        let file_name = item.file_name().into_string().unwrap();
-        if !state.show_dotfiles && file_name.starts_with('.') {
+        let file_name = item.file_name();
+        if !state.show_dotfiles && file_name.as_bytes().starts_with(b".") {
             continue;
         }
-        let metadata = item.metadata()?;
-        let mut kind = EntryKind::File;
-        if metadata.is_dir() {
-            kind = EntryKind::Dir;
-        }
-        if metadata.is_symlink() {
-            kind = EntryKind::Symlink;
-        }
-        let created = match metadata.created() {
-            Ok(time) => Some(time),
-            Err(_) => None,
-        };
-        let size = metadata.len();
-        let entry = Entry {
-            file_name,
-            kind,
-            created,
-            size: size as usize,
-        };
-        match entry.kind {
-            EntryKind::File => files.push(entry),
-            EntryKind::Dir => dirs.push(entry),
-            EntryKind::Symlink => symlinks.push(entry),
+        // entries removed in the meantime, like by a background job, are skipped
+        match Entry::read(file_name, &item.path()) {
+            Ok(entry) => list.push(entry),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err),
         }
     }
-    let mut list = Vec::new();
-    list.extend_from_slice(&dirs);
-    list.extend_from_slice(&symlinks);
-    list.extend_from_slice(&files);
     state.list = list;
+    sort_list(state);
+    Ok(())
+}
+
+// Get terminal column of the cursor behind the prompt title
+fn get_cursor_column(state: &State) -> usize {
+    let title = state.title.clone().unwrap_or_default();
+    let input = state.input.clone().unwrap_or_default();
+    3 + text::width(&title) + 1 + text::width(input.get(..state.cursor).unwrap_or(&input))
+}
+
+// Prints the screen and puts the cursor back into the prompt
+fn redraw(state: &mut State) -> Result<()> {
+    if state.mode != Mode::Prompt {
+        return print(state);
+    }
+    state.term.hide_cursor()?;
+    print(state)?;
+    state.term.move_cursor_to(get_cursor_column(state), 1)?;
+    state.term.show_cursor()?;
     Ok(())
 }
 
@@ -572,26 +1709,32 @@ fn print(state: &mut State) -> Result<()> {
         }
         if i == 3 {
             state.term.write_str("   ")?;
-            for column in &state.columns {
-                let width = column.get_width();
+            for (heading, width, align) in get_headings(state) {
+                let heading = layout::fit(&heading, width, align, false);
                 state
                     .term
-                    .write_str(pad!(&format!("{}", column), width, width - 2))?;
+                    .write_str(&theme::paint(&state.theme.headings, &heading))?;
             }
         }
         if i == 4 {
             let mut total_width = 0;
-            for column in &state.columns {
-                total_width += column.get_width();
+            for (_, width, _) in get_headings(state) {
+                total_width += width;
             }
-            state
-                .term
-                .write_str(&format!("   {}", "-".repeat(total_width)))?;
+            let line = theme::paint(&state.theme.headings, &"-".repeat(total_width));
+            state.term.write_str(&format!("   {}", line))?;
         }
         if i > 4 && i < lines - 2 {
+            if let Some(line) = get_menu_line(state, i - 5) {
+                state.term.write_line(&line)?;
+                continue;
+            }
             let index = i - 5 + state.offset;
             if state.list.len() > index {
-                print_entry(state, index)?;
+                match state.trash.is_some() {
+                    true => print_trash_entry(state, index)?,
+                    false => print_entry(state, index)?,
+                }
                 continue;
             }
         }
@@ -605,11 +1748,154 @@ fn print(state: &mut State) -> Result<()> {
     Ok(())
 }
 
+// Get line of the menu popup drawn over the list, or none below the popup
+fn get_menu_line(state: &State, row: usize) -> Option<String> {
+    let items = state.menu.as_ref()?;
+    let width = items
+        .iter()
+        .map(|item| text::width(item))
+        .max()
+        .unwrap_or_default()
+        + 4;
+    let line = match row {
+        0 => format!("┌{}┐", "─".repeat(width)),
+        row if row <= items.len() => {
+            let item = pad!(&items[row - 1], width - 3);
+            match row - 1 == state.menu_index {
+                true => format!(
+                    "│{}{}│",
+                    theme::paint(&state.theme.caret, " > "),
+                    theme::paint(&state.theme.selection, item)
+                ),
+                false => format!("│   {}│", item),
+            }
+        }
+        row if row == items.len() + 1 => format!("└{}┘", "─".repeat(width)),
+        _ => return None,
+    };
+    Some(format!("   {}", line))
+}
+
+fn get_headings(state: &State) -> Vec<(String, usize, Align)> {
+    match state.trash {
+        Some(_) => vec![
+            (
+                Column::Name.to_string(),
+                Column::Name.get_width(),
+                Align::Left,
+            ),
+            ("ORIGINAL PATH".to_string(), TRASH_PATH_WIDTH, Align::Left),
+            (
+                "DELETED".to_string(),
+                Column::Created.get_width(),
+                Align::Left,
+            ),
+        ],
+        None => {
+            // the indicator is put on the name column if the key is not shown
+            let target = match state.sort.get_column() {
+                Some(column) if state.columns.contains(&column) => column,
+                _ => Column::Name,
+            };
+            let arrow = match state.reverse {
+                true => "▼",
+                false => "▲",
+            };
+            get_layout(state)
+                .into_iter()
+                .map(|(column, width, align)| {
+                    let heading = match column == target {
+                        true if state.sort.get_column().as_ref() == Some(&column) => {
+                            format!("{} {}", column, arrow)
+                        }
+                        true => format!(
+                            "{} ({}) {}",
+                            column,
+                            state.sort.to_string().to_uppercase(),
+                            arrow
+                        ),
+                        false => column.to_string(),
+                    };
+                    (heading, width, align)
+                })
+                .collect()
+        }
+    }
+}
+
+// Get visible columns with their widths and alignments, where the name column
+// fills the terminal width and the columns with the lowest priority are
+// dropped if the terminal is too narrow in the auto layout
+fn get_layout(state: &State) -> Vec<(Column, usize, Align)> {
+    let mut layout: Vec<(Column, usize, Align, usize)> = state
+        .columns
+        .iter()
+        .map(|column| {
+            let configured = state
+                .layouts
+                .iter()
+                .find(|(other, _)| other == column)
+                .map(|(_, column_layout)| column_layout.clone())
+                .unwrap_or_default();
+            (
+                column.clone(),
+                configured.width.unwrap_or_else(|| column.get_width()),
+                configured.align.unwrap_or_else(|| column.get_align()),
+                configured.priority.unwrap_or_else(|| column.get_priority()),
+            )
+        })
+        .collect();
+    if state.auto_layout && layout.iter().any(|(column, ..)| *column == Column::Name) {
+        let (width, _) = state.term.size();
+        // the caret is printed before the columns
+        let available = (width as usize).saturating_sub(3);
+        loop {
+            let fixed: usize = layout
+                .iter()
+                .filter(|(column, ..)| *column != Column::Name)
+                .map(|(_, width, ..)| width)
+                .sum();
+            if fixed + MIN_NAME_WIDTH <= available {
+                break;
+            }
+            let lowest = layout
+                .iter()
+                .enumerate()
+                .filter(|(_, (column, ..))| *column != Column::Name)
+                .min_by_key(|(_, (.., priority))| *priority)
+                .map(|(index, _)| index);
+            match lowest {
+                Some(index) => layout.remove(index),
+                None => break,
+            };
+        }
+        let fixed: usize = layout
+            .iter()
+            .filter(|(column, ..)| *column != Column::Name)
+            .map(|(_, width, ..)| width)
+            .sum();
+        for (column, width, ..) in layout.iter_mut() {
+            if *column == Column::Name {
+                *width = available.saturating_sub(fixed).max(MIN_NAME_WIDTH);
+            }
+        }
+    }
+    layout
+        .into_iter()
+        .map(|(column, width, align, _)| (column, width, align))
+        .collect()
+}
+
 fn print_head(state: &mut State) -> Result<()> {
     match state.mode {
         Mode::Normal => {
-            let path = state.path.display().to_string();
-            state.term.write_line(&format!("   {}", path))?;
+            let path = match state.trash {
+                Some(_) => format!("trash: {}", trash::trash_dir()?.display()),
+                None => state.path.display().to_string(),
+            };
+            state
+                .term
+                .write_line(&format!("   {}", theme::paint(&state.theme.header, &path)))?;
         }
         Mode::Prompt => {
             state.term.write_line(&format!(
@@ -618,51 +1904,131 @@ fn print_head(state: &mut State) -> Result<()> {
                 state.input.clone().unwrap_or_default(),
             ))?;
         }
+        Mode::Dialog => {
+            state.term.write_line(&format!(
+                "   {} {}",
+                theme::paint(&state.theme.title, &state.title.clone().unwrap_or_default()),
+                get_hint(state, Context::Dialog, &DIALOG_CHOICES),
+            ))?;
+        }
+        Mode::Menu => {
+            state.term.write_line(&format!(
+                "   {} {}",
+                theme::paint(&state.theme.title, &state.title.clone().unwrap_or_default()),
+                get_hint(state, Context::Menu, &MENU_CHOICES),
+            ))?;
+        }
     }
     Ok(())
 }
 
+// Get hint listing the keys of the choices like `[o] overwrite [s] skip`
+fn get_hint(state: &State, context: Context, choices: &[(Action, &str)]) -> String {
+    let hints: Vec<String> = choices
+        .iter()
+        .filter_map(|(action, name)| {
+            let sequence = state.keymap.get_sequence(context, *action)?;
+            Some(format!("[{}] {}", sequence, name))
+        })
+        .collect();
+    hints.join(" ")
+}
+
 fn print_entry(state: &mut State, index: usize) -> Result<()> {
     let entry = &state.list[index];
     if state.mode == Mode::Normal && state.index == index {
-        state.term.write_str(" > ")?;
+        state
+            .term
+            .write_str(&theme::paint(&state.theme.caret, " > "))?;
     } else {
         state.term.write_str("   ")?;
     };
-    let color = match entry.kind {
-        EntryKind::File => Color::White,
-        EntryKind::Dir => Color::Blue,
-        EntryKind::Symlink => Color::Magenta,
-    };
-    for column in &state.columns {
-        let width = column.get_width();
-        let value = pad!(
-            match column {
-                Column::Name => entry.file_name.clone(),
-                Column::Type => (match entry.kind {
-                    EntryKind::File => "file",
-                    EntryKind::Dir => "dir",
-                    EntryKind::Symlink => "symlink",
-                })
-                .to_string(),
-                Column::Size => {
-                    entry.size.to_string()
-                }
-                Column::Created => match entry.created {
-                    Some(time) => {
-                        let datetime: DateTime<Local> = time.into();
-                        datetime.format("%d.%m.%Y %I:%M %P").to_string()
-                    }
-                    None => "".to_string(),
-                },
-            },
-            width,
-            width - 2
-        );
-        match state.selected.contains(&index) {
-            true => state.term.write_str(color!(&value, Color::Black, color))?,
-            false => state.term.write_str(color!(&value, color))?,
-        };
+    let codes = match state.selected.contains(&index) {
+        true => theme::combine(state.theme.get_entry(entry), &state.theme.selection),
+        false => state.theme.get_entry(entry).to_string(),
+    };
+    for (column, width, align) in get_layout(state) {
+        let value = get_value(state, entry, &column);
+        let value = layout::fit(&value, width, align, column == Column::Name);
+        state.term.write_str(&theme::paint(&codes, &value))?;
+    }
+    state.term.write_line("")?;
+    Ok(())
+}
+
+// Get text of the entry shown in the column
+fn get_value(state: &State, entry: &Entry, column: &Column) -> String {
+    let format_time = |time: Option<SystemTime>| match time {
+        Some(time) => state
+            .dates
+            .format_time(time, state.dates.get_format(column)),
+        None => "".to_string(),
+    };
+    match column {
+        Column::Name => entry.file_name.clone(),
+        Column::Type => (match entry.kind {
+            EntryKind::File => "file",
+            EntryKind::Dir => "dir",
+            EntryKind::Symlink => "symlink",
+        })
+        .to_string(),
+        // the size of a directory itself is meaningless, so only computed sizes are shown
+        Column::Size if entry.is_dir() => {
+            let path = entry.get_path(&state.path);
+            match state.sizes.get(&path) {
+                _ if state.sizes.is_pending(&path) => "...".to_string(),
+                Some(bytes) => format_size(bytes, state.size_format),
+                None => "".to_string(),
+            }
+        }
+        Column::Size => format_size(entry.size as u64, state.size_format),
+        Column::Created => format_time(entry.created),
+        Column::Modified => format_time(entry.modified),
+        Column::Accessed => format_time(entry.accessed),
+        Column::Permissions => entry.get_permissions(),
+        Column::Owner => users::get_user_name(entry.uid),
+        Column::Group => users::get_group_name(entry.gid),
+        Column::Inode => entry.inode.to_string(),
+        Column::Links => entry.links.to_string(),
+        Column::Target => match &entry.target {
+            Some(target) => target.display().to_string(),
+            None => "".to_string(),
+        },
+    }
+}
+
+fn print_trash_entry(state: &mut State, index: usize) -> Result<()> {
+    let entry = &state.list[index];
+    let item = &state.trash.as_ref().unwrap()[index];
+    if state.mode == Mode::Normal && state.index == index {
+        state
+            .term
+            .write_str(&theme::paint(&state.theme.caret, " > "))?;
+    } else {
+        state.term.write_str("   ")?;
+    };
+    let codes = match state.selected.contains(&index) {
+        true => theme::combine(state.theme.get_entry(entry), &state.theme.selection),
+        false => state.theme.get_entry(entry).to_string(),
+    };
+    // deletion dates are stored in local time
+    let deleted = match item
+        .deleted
+        .map(|datetime| Local.from_local_datetime(&datetime))
+    {
+        Some(LocalResult::Single(datetime)) => state
+            .dates
+            .format_time(datetime.into(), state.dates.get_deleted_format()),
+        _ => "".to_string(),
+    };
+    let values = [
+        (entry.file_name.clone(), Column::Name.get_width()),
+        (item.path.display().to_string(), TRASH_PATH_WIDTH),
+        (deleted, Column::Created.get_width()),
+    ];
+    for (index, (value, width)) in values.into_iter().enumerate() {
+        let value = &layout::fit(&value, width, Align::Left, index == 0);
+        state.term.write_str(&theme::paint(&codes, value))?;
     }
     state.term.write_line("")?;
     Ok(())
@@ -672,22 +2038,42 @@ fn print_message(state: &mut State) -> Result<()> {
     let length = state.list.len();
     let digits = length.to_string().len();
     let index = if length == 0 { 0 } else { state.index + 1 };
-    match &state.message {
-        Some(message) => state.term.write_line(&format!(
-            "   {:0>width$}/{}   {} sel   {}",
-            index,
-            length,
-            state.selected.len(),
-            message,
-            width = digits,
-        ))?,
-        None => state.term.write_line(&format!(
-            "   {:0>width$}/{}   {} sel",
-            index,
-            length,
-            state.selected.len(),
-            width = digits
-        ))?,
+    let mut line = format!(
+        "   {:0>width$}/{}   {} sel",
+        index,
+        length,
+        state.selected.len(),
+        width = digits
+    );
+    if let Some(status) = &state.jobs.status {
+        line.push_str(&format!(
+            "   {}",
+            format_job(status, state.jobs.get_queued(), state.size_format)
+        ));
     }
+    if let Some(message) = &state.message {
+        let codes = state.theme.get_level(&message.level);
+        line.push_str(&format!("   {}", theme::paint(codes, &message.text)));
+    }
+    state.term.write_line(&line)?;
     Ok(())
 }
+
+fn format_job(status: &JobStatus, queued: usize, size_format: SizeFormat) -> String {
+    let mut text = format!(
+        "[{}%] {}/{} files {}/{}",
+        status.get_percent(),
+        status.files,
+        status.total_files,
+        format_size(status.bytes, size_format),
+        format_size(status.total_bytes, size_format),
+    );
+    if let Some(eta) = status.get_eta() {
+        let secs = eta.as_secs();
+        text.push_str(&format!(" {}:{:02} left", secs / 60, secs % 60));
+    }
+    if queued > 0 {
+        text.push_str(&format!(" (+{} queued)", queued));
+    }
+    text
+}
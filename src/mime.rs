@@ -0,0 +1,124 @@
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// The number of bytes read for detecting the type of a file
+const SNIFF_SIZE: usize = 1024;
+
+// The size of the bitmap file header preceding the info header
+const BMP_FILE_HEADER: u32 = 14;
+// The sizes of the known bitmap info headers, from the `BITMAPCOREHEADER`
+// to the `BITMAPV5HEADER`
+const BMP_INFO_HEADERS: [u32; 7] = [12, 40, 52, 56, 64, 108, 124];
+
+// The magic bytes at the start of files and their mime types
+const MAGIC: [(&[u8], &str); 21] = [
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"BZh", "application/x-bzip2"),
+    (b"\xfd7zXZ\x00", "application/x-xz"),
+    (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"\x7fELF", "application/x-executable"),
+    (b"\x00asm", "application/wasm"),
+    (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    (b"ID3", "audio/mpeg"),
+    (b"OggS", "audio/ogg"),
+    (b"fLaC", "audio/flac"),
+    (b"\x1a\x45\xdf\xa3", "video/x-matroska"),
+    (b"%!PS", "application/postscript"),
+    (b"{\\rtf", "application/rtf"),
+    (b"#!", "text/x-shellscript"),
+];
+
+// Detects the mime type of a file by its content
+pub fn detect(path: &Path) -> String {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return "inode/x-broken".into(),
+    };
+    if metadata.is_dir() {
+        return "inode/directory".into();
+    }
+    let mut buffer = Vec::with_capacity(SNIFF_SIZE);
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(SNIFF_SIZE as u64).read_to_end(&mut buffer);
+    }
+    sniff(&buffer).into()
+}
+
+// Get mime type for the leading bytes of a file
+pub fn sniff(bytes: &[u8]) -> &'static str {
+    for (magic, mime) in MAGIC {
+        if bytes.starts_with(magic) {
+            return mime;
+        }
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" {
+        match &bytes[8..12] {
+            b"WEBP" => return "image/webp",
+            b"WAVE" => return "audio/wav",
+            b"AVI " => return "video/x-msvideo",
+            _ => (),
+        }
+    }
+    if is_bmp(bytes) {
+        return "image/bmp";
+    }
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        return "video/mp4";
+    }
+    if bytes.len() >= 262 && &bytes[257..262] == b"ustar" {
+        return "application/x-tar";
+    }
+    if is_text(bytes) {
+        return "text/plain";
+    }
+    "application/octet-stream"
+}
+
+// Checks if a mime type matches a pattern like `text/plain`, `text/*` or `*`
+pub fn matches(pattern: &str, mime: &str) -> bool {
+    if pattern == "*" || pattern == "*/*" {
+        return true;
+    }
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => mime.split('/').next() == Some(prefix),
+        None => pattern == mime,
+    }
+}
+
+// Checks the bitmap header, as its magic `BM` alone is common in text files
+fn is_bmp(bytes: &[u8]) -> bool {
+    if bytes.len() < 18 || &bytes[..2] != b"BM" {
+        return false;
+    }
+    let read_u32 = |start: usize| {
+        u32::from_le_bytes([
+            bytes[start],
+            bytes[start + 1],
+            bytes[start + 2],
+            bytes[start + 3],
+        ])
+    };
+    let file_size = read_u32(2);
+    let info_size = read_u32(14);
+    BMP_INFO_HEADERS.contains(&info_size) && file_size >= BMP_FILE_HEADER + info_size
+}
+
+fn is_text(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        // the buffer may end within a multi-byte character
+        Err(err) => err.error_len().is_none(),
+    }
+}
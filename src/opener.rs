@@ -0,0 +1,256 @@
+use glob::Pattern;
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+
+use crate::expand_tilde;
+use crate::mime;
+use crate::shell;
+use crate::Entry;
+use crate::EntryKind;
+use crate::Error;
+use crate::Result;
+
+// The placeholder for the opened files in an app command
+const PLACEHOLDER: &str = "{}";
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Opener {
+    // The app command, which is the key in the legacy apps table
+    #[serde(default)]
+    pub app: String,
+    // The file extensions opened by the app
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    // The mime types opened by the app, like `text/plain` or `text/*`
+    #[serde(default)]
+    pub mimes: Vec<String>,
+    // The glob matching the file name, like `*.test.ts`
+    pub glob: Option<String>,
+    // The regex matching the full file name
+    pub regex: Option<String>,
+    // The kind of entries opened by the app
+    pub kind: Option<EntryKind>,
+    // The path prefix of entries opened by the app
+    pub path: Option<PathBuf>,
+    // The flag if the app command is run by a shell
+    #[serde(default)]
+    pub shell: bool,
+    // The flag if multiple files are opened by a single process
+    #[serde(default)]
+    pub multiple: bool,
+    // The flag if the app is a graphical app, which is started detached
+    // instead of taking over the terminal
+    #[serde(default)]
+    pub gui: bool,
+    // The compiled glob, set when the opener is compiled
+    #[serde(skip)]
+    pub(crate) pattern: Option<Pattern>,
+    // The compiled regex anchored to the full file name
+    #[serde(skip)]
+    pub(crate) re: Option<Regex>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AppConfig {
+    Extensions(Vec<String>),
+    Opener(Box<Opener>),
+}
+
+impl AppConfig {
+    // Get opener for the app command
+    pub fn get_opener(&self, app: &str) -> Opener {
+        let mut opener = match self {
+            Self::Extensions(extensions) => Opener {
+                extensions: extensions.clone(),
+                ..Opener::default()
+            },
+            Self::Opener(opener) => opener.as_ref().clone(),
+        };
+        opener.app = app.to_string();
+        opener
+    }
+}
+
+impl Opener {
+    pub fn new(app: &str) -> Self {
+        Self {
+            app: app.to_string(),
+            ..Self::default()
+        }
+    }
+    // Checks if all conditions of the opener match the entry, where the
+    // mime type is only detected once it is needed
+    pub fn matches(&self, entry: &Entry, dir: &Path, mime: &mut Option<String>) -> bool {
+        if !self.extensions.is_empty() {
+            let file_ext = entry.get_extension();
+            if !self
+                .extensions
+                .iter()
+                .any(|ext| ext.to_lowercase() == file_ext)
+            {
+                return false;
+            }
+        }
+        if !self.mimes.is_empty() {
+            let mime = mime.get_or_insert_with(|| entry.get_mime(dir));
+            if !self
+                .mimes
+                .iter()
+                .any(|pattern| mime::matches(pattern, mime))
+            {
+                return false;
+            }
+        }
+        // openers which were not compiled compile their patterns on every match
+        if let Some(glob) = &self.glob {
+            let matched = match &self.pattern {
+                Some(pattern) => pattern.matches(&entry.file_name),
+                None => compile_glob(glob).is_ok_and(|pattern| pattern.matches(&entry.file_name)),
+            };
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(regex) = &self.regex {
+            let matched = match &self.re {
+                Some(re) => re.is_match(&entry.file_name),
+                None => compile_regex(regex).is_ok_and(|re| re.is_match(&entry.file_name)),
+            };
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(kind) = &self.kind {
+            if kind != &entry.kind {
+                return false;
+            }
+        }
+        if let Some(path) = &self.path {
+            match expand_tilde(path.clone()) {
+                Some(prefix) if entry.get_path(dir).starts_with(&prefix) => (),
+                _ => return false,
+            }
+        }
+        true
+    }
+    // Compiles the glob and regex of the opener once, so that matching
+    // entries reuses them
+    pub fn compile(&mut self) -> Result<()> {
+        if let Some(glob) = &self.glob {
+            self.pattern = Some(compile_glob(glob)?);
+        }
+        if let Some(regex) = &self.regex {
+            self.re = Some(compile_regex(regex)?);
+        }
+        Ok(())
+    }
+    // Checks if the opener has an app
+    pub fn validate(&self) -> Result<()> {
+        if self.app.trim().is_empty() {
+            return Err(Error::new("Opener without app!"));
+        }
+        Ok(())
+    }
+    // Builds the commands opening the files, either one for all files
+    // or one for each file
+    pub fn build_all(&self, files: &[PathBuf]) -> Result<Vec<Command>> {
+        match self.multiple {
+            true => Ok(vec![self.build(files)?]),
+            false => files
+                .iter()
+                .map(|file| self.build(std::slice::from_ref(file)))
+                .collect(),
+        }
+    }
+    // Builds the command opening the files, where each `{}` argument is replaced
+    // by the files, or the files are appended if there is no placeholder
+    pub fn build(&self, files: &[PathBuf]) -> Result<Command> {
+        if self.shell {
+            let quoted: Vec<OsString> = files
+                .iter()
+                .map(|file| shell::quote_os(file.as_os_str()))
+                .collect();
+            let quoted = quoted.join(OsStr::new(" "));
+            let script = match self.app.contains(PLACEHOLDER) {
+                true => shell::replace_os(&self.app, PLACEHOLDER, &quoted),
+                false => shell::replace_os(
+                    &format!("{} {}", self.app, PLACEHOLDER),
+                    PLACEHOLDER,
+                    &quoted,
+                ),
+            };
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(script);
+            return Ok(command);
+        }
+        let words = match shell::split(&self.app) {
+            Some(words) if !words.is_empty() => words,
+            _ => return Err(Error::new(&format!("Invalid app '{}'!", self.app))),
+        };
+        let mut args = Vec::new();
+        let mut replaced = false;
+        for word in &words[1..] {
+            if word == PLACEHOLDER {
+                args.extend(files.iter().map(|file| file.as_os_str().to_os_string()));
+                replaced = true;
+            } else if word.contains(PLACEHOLDER) {
+                for file in files {
+                    args.push(shell::replace_os(word, PLACEHOLDER, file.as_os_str()));
+                }
+                replaced = true;
+            } else {
+                args.push(word.into());
+            }
+        }
+        if !replaced {
+            args.extend(files.iter().map(|file| file.as_os_str().to_os_string()));
+        }
+        let mut command = Command::new(&words[0]);
+        command.args(args);
+        Ok(command)
+    }
+}
+
+// Starts the command in its own process group without any terminal io
+pub fn spawn_detached(command: &mut Command) -> io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    // wait for the child in the background, so that it does not remain as a zombie
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+fn compile_glob(glob: &str) -> Result<Pattern> {
+    match Pattern::new(glob) {
+        Ok(pattern) => Ok(pattern),
+        Err(err) => Err(Error::new(&format!(
+            "Invalid glob '{}'! Reason: {}",
+            glob, err
+        ))),
+    }
+}
+
+fn compile_regex(regex: &str) -> Result<Regex> {
+    match Regex::new(&format!("^(?:{})$", regex)) {
+        Ok(re) => Ok(re),
+        Err(err) => Err(Error::new(&format!(
+            "Invalid regex '{}'! Reason: {}",
+            regex, err
+        ))),
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+use std::env;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::symlink;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::text;
+use crate::Error;
+use crate::Result;
+
+// The size of the buffer used for copying files
+const BUFFER_SIZE: usize = 64 * 1024;
+
+// Receives the number of copied bytes and files, returning an error aborts the copy
+pub type Progress<'a> = dyn FnMut(u64, usize) -> Result<()> + 'a;
+
+// Copies a file, directory or symlink to the destination path
+pub fn copy(src: &Path, dest: &Path) -> Result<()> {
+    copy_with(src, dest, &mut |_, _| Ok(()))
+}
+
+// Copies a file, directory or symlink and reports the progress
+pub fn copy_with(src: &Path, dest: &Path, progress: &mut Progress) -> Result<()> {
+    if dest.starts_with(src) {
+        return Err(Error::new("Unable to copy a directory into itself!"));
+    }
+    if dest.symlink_metadata().is_ok() {
+        return Err(Error::new(&format!("'{}' already exists!", dest.display())));
+    }
+    if let Err(err) = copy_recursive(src, dest, progress) {
+        // do not leave a partial copy behind
+        if dest.symlink_metadata().is_ok() {
+            remove(dest)?;
+        }
+        return Err(err);
+    }
+    Ok(())
+}
+
+// Moves a file, directory or symlink to the destination path
+pub fn rename(src: &Path, dest: &Path) -> Result<()> {
+    rename_with(src, dest, &mut |_, _| Ok(()))
+}
+
+// Moves a file, directory or symlink and reports the progress
+pub fn rename_with(src: &Path, dest: &Path, progress: &mut Progress) -> Result<()> {
+    if dest.starts_with(src) {
+        return Err(Error::new("Unable to move a directory into itself!"));
+    }
+    match fs::rename(src, dest) {
+        Ok(_) => Ok(()),
+        // rename fails across filesystems, so fall back to copy and remove
+        Err(err) if err.raw_os_error() == Some(libc::EXDEV) => {
+            copy_with(src, dest, progress)?;
+            remove(src)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+// Get a path next to the given path which does not exist yet
+pub fn unique_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = match path.extension() {
+        Some(ext) => format!(".{}", ext.to_string_lossy()),
+        None => String::new(),
+    };
+    let mut counter = 1;
+    loop {
+        let unique = path.with_file_name(format!("{}_{}{}", stem, counter, ext));
+        if unique.symlink_metadata().is_err() {
+            return unique;
+        }
+        counter += 1;
+    }
+}
+
+// Get flag if the first path was modified more recently than the second one
+pub fn is_newer(path: &Path, other: &Path) -> bool {
+    let modified =
+        |path: &Path| fs::symlink_metadata(path).and_then(|metadata| metadata.modified());
+    match (modified(path), modified(other)) {
+        (Ok(modified), Ok(other)) => modified > other,
+        _ => false,
+    }
+}
+
+// Get flag if the entry is moved without copying it, which is the case if
+// the destination is on the same filesystem
+pub fn is_same_filesystem(src: &Path, dest: &Path) -> bool {
+    let device = match fs::symlink_metadata(src) {
+        Ok(metadata) => metadata.dev(),
+        Err(_) => return true,
+    };
+    // the destination does not exist yet, so its closest existing parent is checked
+    for parent in dest.ancestors().skip(1) {
+        if let Ok(metadata) = fs::metadata(parent) {
+            return metadata.dev() == device;
+        }
+    }
+    true
+}
+
+// Get total size in bytes and number of files of a file or directory
+pub fn measure(path: &Path) -> (u64, usize) {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return (0, 0),
+    };
+    if !metadata.is_dir() {
+        return (metadata.len(), 1);
+    }
+    let mut total = (0, 0);
+    if let Ok(dir) = fs::read_dir(path) {
+        for item in dir.flatten() {
+            let (bytes, files) = measure(&item.path());
+            total.0 += bytes;
+            total.1 += files;
+        }
+    }
+    total
+}
+
+// Renames entries within a directory, allowing entries to swap their names
+pub fn rename_all(dir: &Path, renames: &[(OsString, OsString)]) -> Result<usize> {
+    let renames: Vec<&(OsString, OsString)> =
+        renames.iter().filter(|(from, to)| from != to).collect();
+    let mut names = HashSet::new();
+    for (_, to) in &renames {
+        validate_name(to)?;
+        if !names.insert(to) {
+            return Err(Error::new(&format!(
+                "Duplicate file name '{}'!",
+                text::escape(to)
+            )));
+        }
+        if dir.join(to).symlink_metadata().is_ok() && !renames.iter().any(|(from, _)| from == to) {
+            return Err(Error::new(&format!(
+                "'{}' already exists!",
+                text::escape(to)
+            )));
+        }
+    }
+    // move entries to temporary names first, so that swapped names do not collide
+    let mut temps = Vec::new();
+    for (i, (from, _)) in renames.iter().enumerate() {
+        let temp = dir.join(format!(".fx-rename-{}-{}", process::id(), i));
+        if let Err(err) = fs::rename(dir.join(from), &temp) {
+            restore_names(dir, &renames, &temps);
+            return Err(err.into());
+        }
+        temps.push(temp);
+    }
+    for (i, (temp, (_, to))) in temps.iter().zip(&renames).enumerate() {
+        if let Err(err) = fs::rename(temp, dir.join(to)) {
+            for (temp, (_, to)) in temps.iter().zip(&renames).take(i) {
+                let _ = fs::rename(dir.join(to), temp);
+            }
+            restore_names(dir, &renames, &temps);
+            return Err(err.into());
+        }
+    }
+    Ok(renames.len())
+}
+
+// Moves entries from their temporary names back to their original names,
+// which is done on a best effort basis since an error is reported anyway
+fn restore_names(dir: &Path, renames: &[&(OsString, OsString)], temps: &[PathBuf]) {
+    for (temp, (from, _)) in temps.iter().zip(renames) {
+        let _ = fs::rename(temp, dir.join(from));
+    }
+}
+
+// Checks if every component of the relative path can be used as a file name,
+// so that the path cannot leave the directory it is joined to
+pub fn validate_path(path: &str) -> Result<()> {
+    for name in path.split('/') {
+        if let Err(err) = validate_name(name) {
+            return Err(Error::new(&format!(
+                "Invalid path '{}'! Reason: {}",
+                path, err
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Creates a new file which only the user can access, choosing a name which
+// does not exist yet so that no existing file or symlink is followed
+pub fn create_temp(prefix: &str, suffix: &str) -> Result<(PathBuf, File)> {
+    let dir = env::temp_dir();
+    for attempt in 0..100 {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.subsec_nanos())
+            .unwrap_or_default();
+        let path = dir.join(format!(
+            "{}-{}-{:08x}{}{}",
+            prefix,
+            process::id(),
+            nanos,
+            attempt,
+            suffix
+        ));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path);
+        match file {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Err(Error::new("Unable to create temporary file!"))
+}
+
+// Checks if the name can be used as a file name
+pub fn validate_name<S: AsRef<OsStr>>(name: S) -> Result<()> {
+    let name = name.as_ref();
+    if name.is_empty() || name == "." || name == ".." || name.as_bytes().contains(&b'/') {
+        return Err(Error::new(&format!(
+            "Invalid file name '{}'!",
+            text::escape(name)
+        )));
+    }
+    Ok(())
+}
+
+// Creates a new empty file
+pub fn create_file(path: &Path) -> Result<()> {
+    OpenOptions::new().write(true).create_new(true).open(path)?;
+    Ok(())
+}
+
+// Creates a new directory including all missing parent directories
+// and returns the top-most directory which was created
+pub fn create_dir(path: &Path) -> Result<PathBuf> {
+    if path.symlink_metadata().is_ok() {
+        return Err(Error::new("Entry already exists!"));
+    }
+    let mut root = path;
+    while let Some(parent) = root.parent() {
+        if parent.symlink_metadata().is_ok() {
+            break;
+        }
+        root = parent;
+    }
+    let root = root.to_path_buf();
+    fs::create_dir_all(path)?;
+    Ok(root)
+}
+
+// Creates a new symlink pointing at the target path
+pub fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    symlink(target, link)?;
+    Ok(())
+}
+
+// Removes a file, directory or symlink permanently
+pub fn remove(path: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn copy_recursive(src: &Path, dest: &Path, progress: &mut Progress) -> Result<()> {
+    let metadata = fs::symlink_metadata(src)?;
+    if metadata.is_symlink() {
+        symlink(fs::read_link(src)?, dest)?;
+        progress(metadata.len(), 1)?;
+    } else if metadata.is_dir() {
+        fs::create_dir(dest)?;
+        for dir_entry in fs::read_dir(src)? {
+            let item = dir_entry?;
+            copy_recursive(&item.path(), &dest.join(item.file_name()), progress)?;
+        }
+        fs::set_permissions(dest, metadata.permissions())?;
+    } else {
+        let mut reader = File::open(src)?;
+        let mut writer = OpenOptions::new().write(true).create_new(true).open(dest)?;
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            let count = reader.read(&mut buffer)?;
+            if count == 0 {
+                break;
+            }
+            writer.write_all(&buffer[..count])?;
+            progress(count as u64, 0)?;
+        }
+        fs::set_permissions(dest, metadata.permissions())?;
+        progress(0, 1)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::ffi::OsStringExt;
+use std::path::Path;
+
+// Quotes a string so that a POSIX shell treats it as a single word
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+// Quotes a raw value like a file name, which may not be valid UTF-8,
+// so that a POSIX shell treats it as a single word
+pub fn quote_os(value: &OsStr) -> OsString {
+    let mut quoted = vec![b'\''];
+    for byte in value.as_bytes() {
+        match byte {
+            b'\'' => quoted.extend_from_slice(b"'\\''"),
+            byte => quoted.push(*byte),
+        }
+    }
+    quoted.push(b'\'');
+    OsString::from_vec(quoted)
+}
+
+// Replaces the placeholder in the text by the raw value
+pub fn replace_os(text: &str, placeholder: &str, value: &OsStr) -> OsString {
+    let mut replaced = OsString::new();
+    for (i, part) in text.split(placeholder).enumerate() {
+        if i > 0 {
+            replaced.push(value);
+        }
+        replaced.push(part);
+    }
+    replaced
+}
+
+// Expands the placeholders of a shell command:
+// %f - the current entry
+// %s - the selected entries or the current entry
+// %b - the current entry without its extension
+// %d - the current directory
+// %% - a literal percent sign
+pub fn expand(
+    command: &str,
+    current: Option<&OsStr>,
+    selected: &[OsString],
+    dir: &Path,
+) -> OsString {
+    let mut expanded = OsString::new();
+    let mut chars = command.chars();
+    while let Some(char) = chars.next() {
+        if char != '%' {
+            expanded.push(char.encode_utf8(&mut [0; 4]));
+            continue;
+        }
+        match chars.next() {
+            Some('f') => expanded.push(quote_os(current.unwrap_or_default())),
+            Some('s') => {
+                let quoted: Vec<OsString> = selected.iter().map(|name| quote_os(name)).collect();
+                expanded.push(quoted.join(OsStr::new(" ")));
+            }
+            Some('b') => {
+                let current = current.unwrap_or_default().as_bytes();
+                let stem = match current.iter().rposition(|byte| *byte == b'.') {
+                    Some(index) if index > 0 => &current[..index],
+                    _ => current,
+                };
+                expanded.push(quote_os(OsStr::from_bytes(stem)));
+            }
+            Some('d') => expanded.push(quote_os(dir.as_os_str())),
+            Some('%') => expanded.push("%"),
+            Some(other) => {
+                expanded.push("%");
+                expanded.push(other.encode_utf8(&mut [0; 4]));
+            }
+            None => expanded.push("%"),
+        }
+    }
+    expanded
+}
+
+// Splits a command into words, honoring single quotes, double quotes and backslashes
+pub fn split(command: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        char => word.push(char),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => word.push(chars.next()?),
+                        char => word.push(char),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).push(chars.next()?),
+            char if char.is_whitespace() => {
+                if let Some(word) = word.take() {
+                    words.push(word);
+                }
+            }
+            char => word.get_or_insert_with(String::new).push(char),
+        }
+    }
+    if let Some(word) = word {
+        words.push(word);
+    }
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    // A name with a quote, spaces and a command substitution, and a name
+    // which is not valid UTF-8
+    const TRICKY: &str = "it's $(touch pwned) `id`";
+    const RAW: &[u8] = b"a\xff b";
+
+    // Get the words a POSIX shell sees when running `printf` with the arguments
+    fn shell_words(arguments: &OsStr) -> Vec<u8> {
+        let mut script = OsString::from("printf '[%s]' ");
+        script.push(arguments);
+        Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .output()
+            .unwrap()
+            .stdout
+    }
+
+    #[test]
+    fn quote_escapes_single_quotes() {
+        assert_eq!(quote("a b"), "'a b'");
+        assert_eq!(quote("it's"), "'it'\\''s'");
+        assert_eq!(quote_os(OsStr::new("it's")), OsStr::new("'it'\\''s'"));
+    }
+
+    #[test]
+    fn quote_os_keeps_single_word() {
+        let tricky = quote_os(OsStr::new(TRICKY));
+        assert_eq!(shell_words(&tricky), format!("[{}]", TRICKY).as_bytes());
+        let raw = quote_os(OsStr::from_bytes(RAW));
+        assert_eq!(shell_words(&raw), [b"[", RAW, b"]"].concat());
+    }
+
+    #[test]
+    fn expand_replaces_placeholders() {
+        let dir = Path::new("/tmp/my dir");
+        let current = OsStr::new("notes.tar.gz");
+        let selected = [OsString::from("a b"), OsString::from("it's")];
+        assert_eq!(
+            expand("tar %f %b %d", Some(current), &selected, dir),
+            OsStr::new("tar 'notes.tar.gz' 'notes.tar' '/tmp/my dir'")
+        );
+        assert_eq!(
+            expand("rm %s", Some(current), &selected, dir),
+            OsStr::new("rm 'a b' 'it'\\''s'")
+        );
+        assert_eq!(
+            expand("echo 100%% %x %", None, &[], dir),
+            OsStr::new("echo 100% %x %")
+        );
+    }
+
+    #[test]
+    fn expand_keeps_dotfile_names() {
+        let dir = Path::new("/tmp");
+        assert_eq!(
+            expand("%b", Some(OsStr::new(".bashrc")), &[], dir),
+            OsStr::new("'.bashrc'")
+        );
+        assert_eq!(
+            expand("%b", Some(OsStr::new(".config.toml")), &[], dir),
+            OsStr::new("'.config'")
+        );
+    }
+
+    #[test]
+    fn expand_passes_raw_names() {
+        let raw = OsStr::from_bytes(RAW);
+        let expanded = expand("%f", Some(raw), &[], Path::new("/tmp"));
+        assert_eq!(shell_words(&expanded), [b"[", RAW, b"]"].concat());
+        let tricky = OsString::from(TRICKY);
+        let expanded = expand("%s", None, &[tricky, raw.into()], Path::new("/tmp"));
+        assert_eq!(
+            shell_words(&expanded),
+            [format!("[{}][", TRICKY).as_bytes(), RAW, b"]"].concat()
+        );
+    }
+
+    #[test]
+    fn split_honors_quotes() {
+        assert_eq!(
+            split("vim -p 'a b' \"c \\\" d\" e\\ f").unwrap(),
+            ["vim", "-p", "a b", "c \" d", "e f"]
+        );
+        assert_eq!(split("  ").unwrap(), Vec::<String>::new());
+        assert_eq!(split("''").unwrap(), [""]);
+        assert!(split("'open").is_none());
+        assert!(split("trailing\\").is_none());
+    }
+}
@@ -0,0 +1,156 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use crate::ops;
+
+// The units of binary and decimal sizes
+const IEC_UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+const SI_UNITS: [&str; 6] = ["B", "kB", "MB", "GB", "TB", "PB"];
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeFormat {
+    // The plain number of bytes
+    Bytes,
+    // The binary units based on 1024, like `KiB`
+    Iec,
+    // The decimal units based on 1000, like `kB`
+    Si,
+}
+
+// Formats a number of bytes like `1.5 MiB`
+pub fn format_size(bytes: u64, format: SizeFormat) -> String {
+    let (base, units) = match format {
+        SizeFormat::Bytes => return bytes.to_string(),
+        SizeFormat::Iec => (1024.0, IEC_UNITS),
+        SizeFormat::Si => (1000.0, SI_UNITS),
+    };
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} {}", bytes, units[0]),
+        _ => format!("{:.1} {}", value, units[unit]),
+    }
+}
+
+// The recursive sizes of directories, computed on a worker thread
+pub struct DirSizes {
+    sender: Sender<(PathBuf, usize)>,
+    receiver: Receiver<(PathBuf, u64, usize)>,
+    // The number of invalidations, which tells outdated results apart
+    generation: usize,
+    // The directories waiting for their size and the generation of their request
+    pending: HashMap<PathBuf, usize>,
+    // The computed sizes by path
+    cache: HashMap<PathBuf, u64>,
+}
+
+impl DirSizes {
+    pub fn new() -> Self {
+        let (sender, paths) = mpsc::channel::<(PathBuf, usize)>();
+        let (results, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for (path, generation) in paths {
+                let (bytes, _) = ops::measure(&path);
+                if results.send((path, bytes, generation)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            sender,
+            receiver,
+            generation: 0,
+            pending: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+    // Queues the directory for computing its size again
+    pub fn request(&mut self, path: PathBuf) {
+        if self.pending.contains_key(&path) {
+            return;
+        }
+        if self.sender.send((path.clone(), self.generation)).is_ok() {
+            self.pending.insert(path, self.generation);
+        }
+    }
+    // Computes all known sizes again, since entries may have changed on disk
+    pub fn refresh(&mut self) {
+        let paths: Vec<PathBuf> = self.cache.keys().chain(self.pending.keys()).cloned().collect();
+        self.recompute(paths);
+    }
+    // Computes the sizes of the directories containing the changed paths,
+    // or lying inside of them, again
+    pub fn invalidate(&mut self, changed: &[PathBuf]) {
+        let paths: Vec<PathBuf> = self
+            .cache
+            .keys()
+            .chain(self.pending.keys())
+            .filter(|path| {
+                changed
+                    .iter()
+                    .any(|changed| changed.starts_with(path) || path.starts_with(changed))
+            })
+            .cloned()
+            .collect();
+        self.recompute(paths);
+    }
+    // Get computed size of the directory
+    pub fn get(&self, path: &Path) -> Option<u64> {
+        self.cache.get(path).copied()
+    }
+    // Get flag if the size of the directory is being computed
+    pub fn is_pending(&self, path: &Path) -> bool {
+        self.pending.contains_key(path)
+    }
+    // Get flag if any size is being computed
+    pub fn is_busy(&self) -> bool {
+        !self.pending.is_empty()
+    }
+    // Receives computed sizes and returns if any size has changed
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((path, bytes, generation)) = self.receiver.try_recv() {
+            // results requested before the last invalidation of the path are outdated
+            if self.pending.get(&path) != Some(&generation) {
+                continue;
+            }
+            self.pending.remove(&path);
+            self.cache.insert(path, bytes);
+            changed = true;
+        }
+        changed
+    }
+    // Requests the sizes of the directories again, ignoring results of earlier requests
+    fn recompute(&mut self, paths: Vec<PathBuf>) {
+        if paths.is_empty() {
+            return;
+        }
+        self.generation += 1;
+        for path in paths {
+            self.cache.remove(&path);
+            self.pending.remove(&path);
+            // removed directories are forgotten instead of measured as empty
+            if path.is_dir() {
+                self.request(path);
+            }
+        }
+    }
+}
+
+impl Default for DirSizes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
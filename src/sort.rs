@@ -0,0 +1,102 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::Entry;
+use crate::EntryKind;
+use crate::Sort;
+
+// Sorts the entries by the sort mode, where equal entries are ordered by name
+// and sizes are given by the function, which knows the computed directory sizes
+pub fn sort_entries(
+    list: &mut [Entry],
+    sort: Sort,
+    reverse: bool,
+    ignore_case: bool,
+    get_size: &dyn Fn(&Entry) -> u64,
+) {
+    list.sort_by(|a, b| {
+        let by_name = || {
+            natural_cmp(&a.file_name, &b.file_name, ignore_case)
+                .then_with(|| a.os_name.cmp(&b.os_name))
+        };
+        let ordering = match sort {
+            Sort::Name => by_name(),
+            Sort::Type => get_rank(a).cmp(&get_rank(b)).then_with(by_name),
+            Sort::Extension => {
+                natural_cmp(get_suffix(a), get_suffix(b), ignore_case).then_with(by_name)
+            }
+            Sort::Size => get_size(a).cmp(&get_size(b)).then_with(by_name),
+            Sort::Created => a.created.cmp(&b.created).then_with(by_name),
+            Sort::Modified => a.modified.cmp(&b.modified).then_with(by_name),
+        };
+        match reverse {
+            true => ordering.reverse(),
+            false => ordering,
+        }
+    });
+}
+
+// Compares names the way humans do, so that `file2` precedes `file10`
+// and `v1.9` precedes `v1.10`
+pub fn natural_cmp(a: &str, b: &str, ignore_case: bool) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        let (x, y) = match (a.peek(), b.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => (*x, *y),
+        };
+        let ordering = match x.is_ascii_digit() && y.is_ascii_digit() {
+            true => cmp_numbers(&take_number(&mut a), &take_number(&mut b)),
+            false => {
+                a.next();
+                b.next();
+                match ignore_case {
+                    true => x.to_lowercase().cmp(y.to_lowercase()),
+                    false => x.cmp(&y),
+                }
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut number = String::new();
+    while let Some(char) = chars.next_if(|char| char.is_ascii_digit()) {
+        number.push(char);
+    }
+    number
+}
+
+// Compares numbers of any length by their value, or by their leading zeros
+fn cmp_numbers(a: &str, b: &str) -> Ordering {
+    let x = a.trim_start_matches('0');
+    let y = b.trim_start_matches('0');
+    x.len()
+        .cmp(&y.len())
+        .then_with(|| x.cmp(y))
+        .then_with(|| b.len().cmp(&a.len()))
+}
+
+// Get rank of the entry kind, listing dirs before symlinks before files
+fn get_rank(entry: &Entry) -> u8 {
+    match entry.kind {
+        EntryKind::Dir => 0,
+        EntryKind::Symlink => 1,
+        EntryKind::File => 2,
+    }
+}
+
+// Get extension of the entry, which dotfiles and names without a dot lack
+fn get_suffix(entry: &Entry) -> &str {
+    match entry.file_name.rsplit_once('.') {
+        Some((stem, file_ext)) if !stem.is_empty() => file_ext,
+        _ => "",
+    }
+}
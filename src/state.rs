@@ -2,15 +2,46 @@ use std::{collections::HashMap, path::PathBuf};
 
 use console::Term;
 
+use crate::dates::DateConfig;
+use crate::jobs::Jobs;
+use crate::keys::Action;
+use crate::keys::Keymap;
+use crate::layout::ColumnLayout;
+use crate::sizes::DirSizes;
+use crate::sizes::SizeFormat;
+use crate::theme::Theme;
+use crate::trash::TrashItem;
+use crate::Choices;
+use crate::Clipboard;
 use crate::Column;
 use crate::Config;
 use crate::Entry;
+use crate::Journal;
 use crate::Message;
 use crate::Mode;
+use crate::Sort;
 
 pub struct State {
     // The config file
     pub config: Config,
+    // The key bindings
+    pub keymap: Keymap,
+    // The last action and its count, which is performed again on repeat
+    pub repeat: Option<(Action, Option<usize>)>,
+    // The colors of the ui elements
+    pub theme: Theme,
+    // The sort mode of the file list
+    pub sort: Sort,
+    // The flag if the file list is sorted in reverse
+    pub reverse: bool,
+    // The flag if names are sorted ignoring their case
+    pub ignore_case: bool,
+    // The units of sizes
+    pub size_format: SizeFormat,
+    // The computed recursive sizes of directories
+    pub sizes: DirSizes,
+    // The formats of the date columns
+    pub dates: DateConfig,
     // The terminal struct
     pub term: Term,
     // The current directory path
@@ -19,6 +50,10 @@ pub struct State {
     pub mode: Mode,
     // The displayable columns
     pub columns: Vec<Column>,
+    // The configured layouts of the columns
+    pub layouts: Vec<(Column, ColumnLayout)>,
+    // The flag if the name column fills the terminal width
+    pub auto_layout: bool,
     // The current index in the file list
     pub index: usize,
     // The list of files in the current directory
@@ -35,7 +70,8 @@ pub struct State {
     pub title: Option<String>,
     // The input field
     pub input: Option<String>,
-    // The cursor index for the input field
+    // The byte index of the cursor in the input field, which is always on
+    // a character boundary
     pub cursor: usize,
     // The flag if dotfiles should be listed
     pub show_dotfiles: bool,
@@ -43,17 +79,54 @@ pub struct State {
     pub history_index: usize,
     // The history
     pub history: HashMap<String, Vec<String>>,
+    // The yanked or cut paths
+    pub clipboard: Option<Clipboard>,
+    // The trashed items while the trash is shown
+    pub trash: Option<Vec<TrashItem>>,
+    // The journal of performed file operations
+    pub journal: Journal,
+    // The queue of background jobs
+    pub jobs: Jobs,
+    // The flag if finished jobs changed the current directory while the
+    // entries were referred to by a prompt, menu or dialog
+    pub reload: bool,
+    // The apps remembered from the open with menu
+    pub choices: Choices,
+    // The items of the shown menu
+    pub menu: Option<Vec<String>>,
+    // The current index in the menu
+    pub menu_index: usize,
 }
 
 impl State {
     pub fn new(config: Config, path: PathBuf) -> Self {
         let columns = config.get_columns();
+        let layouts = config.get_layouts().unwrap_or_default();
+        let auto_layout = config.auto_layout.unwrap_or(false);
+        let keymap = config.get_keymap().unwrap_or_default();
+        let theme = config.get_theme().unwrap_or_default();
+        let sort = config.sort.unwrap_or(Sort::Type);
+        let reverse = config.sort_reverse.unwrap_or(false);
+        let ignore_case = config.sort_ignore_case.unwrap_or(true);
+        let size_format = config.size_format.unwrap_or(SizeFormat::Iec);
+        let dates = config.dates.clone().unwrap_or_default();
         Self {
             config,
+            keymap,
+            repeat: None,
+            theme,
+            sort,
+            reverse,
+            ignore_case,
+            size_format,
+            sizes: DirSizes::new(),
+            dates,
             term: Term::stdout(),
             path,
             mode: Mode::Normal,
             columns,
+            layouts,
+            auto_layout,
             index: 0,
             list: Vec::new(),
             lines: 0,
@@ -66,6 +139,14 @@ impl State {
             show_dotfiles: true,
             history_index: 0,
             history: HashMap::new(),
+            clipboard: None,
+            trash: None,
+            journal: Journal::default(),
+            jobs: Jobs::new(),
+            reload: false,
+            choices: Choices::default(),
+            menu: None,
+            menu_index: 0,
         }
     }
     // Get currently selected entry in list
@@ -75,6 +156,26 @@ impl State {
         }
         Some(&self.list[self.index])
     }
+    // Get indices of the selected entries or the current entry
+    pub fn get_target_indices(&self) -> Vec<usize> {
+        if self.selected.is_empty() {
+            return match self.list.is_empty() {
+                true => Vec::new(),
+                false => vec![self.index],
+            };
+        }
+        let mut selected = self.selected.clone();
+        selected.sort_unstable();
+        selected.dedup();
+        selected
+    }
+    // Get paths of the selected entries or the current entry
+    pub fn get_targets(&self) -> Vec<PathBuf> {
+        self.get_target_indices()
+            .iter()
+            .map(|i| self.list[*i].get_path(&self.path))
+            .collect()
+    }
     // Set message
     pub fn set_message(&mut self, message: Message) {
         self.message = Some(message);
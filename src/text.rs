@@ -0,0 +1,177 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// The joiner gluing emojis into a single glyph
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+// The selector turning the preceding character into an emoji
+const EMOJI_PRESENTATION: char = '\u{fe0f}';
+
+// Get number of terminal cells the text occupies, where wide characters like
+// CJK and emojis take two cells and combining marks take none
+pub fn width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+// Cuts the text off after the given number of cells without splitting
+// a character or separating it from its combining marks
+pub fn truncate(text: &str, max_width: usize) -> String {
+    let mut truncated = String::new();
+    let mut used = 0;
+    for cluster in text.graphemes(true) {
+        let cluster_width = width(cluster);
+        if used + cluster_width > max_width {
+            break;
+        }
+        truncated.push_str(cluster);
+        used += cluster_width;
+    }
+    truncated
+}
+
+// Get end of the text fitting into the given number of cells, counted from
+// the back of the text
+pub fn truncate_start(text: &str, max_width: usize) -> String {
+    let clusters: Vec<&str> = text.graphemes(true).collect();
+    let mut used = 0;
+    let mut start = clusters.len();
+    for (index, cluster) in clusters.iter().enumerate().rev() {
+        let cluster_width = width(cluster);
+        if used + cluster_width > max_width {
+            break;
+        }
+        used += cluster_width;
+        start = index;
+    }
+    clusters[start..].concat()
+}
+
+// Pads the text with spaces to the given number of cells
+pub fn pad(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(self::width(text));
+    format!("{}{}", text, " ".repeat(fill))
+}
+
+// Pads the text with leading spaces to the given number of cells
+pub fn pad_start(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(self::width(text));
+    format!("{}{}", " ".repeat(fill), text)
+}
+
+// Get text of the name, where bytes which are not valid UTF-8 are escaped like `\xff`
+pub fn escape(name: &OsStr) -> String {
+    let mut escaped = String::new();
+    for chunk in name.as_bytes().utf8_chunks() {
+        escaped.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    escaped
+}
+
+// Get byte index of the boundary before the grapheme ending at the index
+pub fn prev_boundary(text: &str, index: usize) -> usize {
+    match text[..index].grapheme_indices(true).next_back() {
+        Some((boundary, _)) => boundary,
+        None => 0,
+    }
+}
+
+// Get byte index of the boundary after the grapheme starting at the index
+pub fn next_boundary(text: &str, index: usize) -> usize {
+    match text[index..].graphemes(true).next() {
+        Some(cluster) => index + cluster.len(),
+        None => text.len(),
+    }
+}
+
+// Get number of cells of the grapheme, where emoji sequences and flags are
+// drawn as a single wide glyph instead of the sum of their characters
+fn grapheme_width(grapheme: &str) -> usize {
+    let sequence = grapheme.chars().nth(1).is_some()
+        && grapheme.chars().any(|char| {
+            char == ZERO_WIDTH_JOINER || char == EMOJI_PRESENTATION || is_regional_indicator(char)
+        });
+    match sequence {
+        true => 2,
+        false => UnicodeWidthStr::width(grapheme),
+    }
+}
+
+fn is_regional_indicator(char: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The name `日本`, an `e` with a combining acute accent, the German flag,
+    // a family emoji and the Hangul syllable `한` written as jamo
+    const CJK: &str = "\u{65e5}\u{672c}";
+    const COMBINED: &str = "e\u{301}";
+    const FLAG: &str = "\u{1f1e9}\u{1f1ea}";
+    const FAMILY: &str = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+    const JAMO: &str = "\u{1112}\u{1161}\u{11ab}";
+
+    #[test]
+    fn width_counts_cells() {
+        assert_eq!(width("abc"), 3);
+        assert_eq!(width(CJK), 4);
+        assert_eq!(width(COMBINED), 1);
+        assert_eq!(width(FLAG), 2);
+        assert_eq!(width(FAMILY), 2);
+        assert_eq!(width(JAMO), 2);
+    }
+
+    #[test]
+    fn truncate_keeps_whole_graphemes() {
+        assert_eq!(truncate(CJK, 3), "\u{65e5}");
+        assert_eq!(
+            truncate(&format!("a{}b", COMBINED), 2),
+            format!("a{}", COMBINED)
+        );
+        assert_eq!(truncate(&format!("{}{}", FLAG, FLAG), 3), FLAG);
+        assert_eq!(truncate(&format!("{}x", FAMILY), 2), FAMILY);
+        assert_eq!(truncate("abc", 0), "");
+    }
+
+    #[test]
+    fn truncate_start_keeps_whole_graphemes() {
+        assert_eq!(truncate_start(CJK, 3), "\u{672c}");
+        assert_eq!(
+            truncate_start(&format!("ab{}", COMBINED), 2),
+            format!("b{}", COMBINED)
+        );
+        assert_eq!(truncate_start(&format!("x{}", FLAG), 2), FLAG);
+        assert_eq!(truncate_start("abc", 5), "abc");
+    }
+
+    #[test]
+    fn pad_fills_cells() {
+        assert_eq!(pad(CJK, 6), format!("{}  ", CJK));
+        assert_eq!(pad(COMBINED, 3), format!("{}  ", COMBINED));
+        assert_eq!(pad_start(FLAG, 4), format!("  {}", FLAG));
+        assert_eq!(pad("abcdef", 3), "abcdef");
+    }
+
+    #[test]
+    fn boundaries_skip_graphemes() {
+        let text = format!("a{}{}{}", COMBINED, FLAG, JAMO);
+        let after_a = 1;
+        let after_combined = after_a + COMBINED.len();
+        let after_flag = after_combined + FLAG.len();
+        assert_eq!(next_boundary(&text, 0), after_a);
+        assert_eq!(next_boundary(&text, after_a), after_combined);
+        assert_eq!(next_boundary(&text, after_combined), after_flag);
+        assert_eq!(next_boundary(&text, after_flag), text.len());
+        assert_eq!(next_boundary(&text, text.len()), text.len());
+        assert_eq!(prev_boundary(&text, text.len()), after_flag);
+        assert_eq!(prev_boundary(&text, after_flag), after_combined);
+        assert_eq!(prev_boundary(&text, after_combined), after_a);
+        assert_eq!(prev_boundary(&text, after_a), 0);
+        assert_eq!(prev_boundary(&text, 0), 0);
+    }
+}
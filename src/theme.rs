@@ -0,0 +1,260 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+
+use crate::Entry;
+use crate::EntryKind;
+use crate::Error;
+use crate::Level;
+use crate::Result;
+
+// The names of the colors in the order of their ansi codes
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+// The names of the text attributes and their ansi codes
+const ATTRIBUTES: [(&str, u8); 7] = [
+    ("bold", 1),
+    ("dim", 2),
+    ("italic", 3),
+    ("underlined", 4),
+    ("blink", 5),
+    ("reverse", 7),
+    ("hidden", 8),
+];
+
+// The styles of the ui elements, written as dotted names like `blue.bold`
+// or `black.on_white`, where numbers select one of 256 colors
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    // The style of the current path
+    pub header: String,
+    // The style of the column headings
+    pub headings: String,
+    // The style of the caret
+    pub caret: String,
+    // The style added to selected entries
+    pub selection: String,
+    // The style of prompt and dialog titles
+    pub title: String,
+    // The style of info messages
+    pub info: String,
+    // The style of warnings
+    pub warn: String,
+    // The style of errors
+    pub error: String,
+    // The style of files
+    pub file: String,
+    // The style of directories
+    pub dir: String,
+    // The style of symlinks
+    pub symlink: String,
+    // The flag if entries are colored by the LS_COLORS variable
+    pub ls_colors: bool,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            header: String::new(),
+            headings: String::new(),
+            caret: String::new(),
+            selection: "reverse".to_string(),
+            title: "yellow".to_string(),
+            info: "white".to_string(),
+            warn: "yellow".to_string(),
+            error: "red".to_string(),
+            file: "white".to_string(),
+            dir: "blue".to_string(),
+            symlink: "magenta".to_string(),
+            ls_colors: true,
+        }
+    }
+}
+
+impl ThemeConfig {
+    // Converts the styles into ansi codes
+    pub fn get_theme(&self) -> Result<Theme> {
+        let ls_colors = match self.ls_colors {
+            true => env::var("LS_COLORS")
+                .map(|value| LsColors::parse(&value))
+                .unwrap_or_default(),
+            false => LsColors::default(),
+        };
+        Ok(Theme {
+            header: parse_style(&self.header)?,
+            headings: parse_style(&self.headings)?,
+            caret: parse_style(&self.caret)?,
+            selection: parse_style(&self.selection)?,
+            title: parse_style(&self.title)?,
+            info: parse_style(&self.info)?,
+            warn: parse_style(&self.warn)?,
+            error: parse_style(&self.error)?,
+            file: parse_style(&self.file)?,
+            dir: parse_style(&self.dir)?,
+            symlink: parse_style(&self.symlink)?,
+            ls_colors,
+        })
+    }
+}
+
+// The ansi codes of the ui elements
+#[derive(Default)]
+pub struct Theme {
+    pub header: String,
+    pub headings: String,
+    pub caret: String,
+    pub selection: String,
+    pub title: String,
+    pub info: String,
+    pub warn: String,
+    pub error: String,
+    pub file: String,
+    pub dir: String,
+    pub symlink: String,
+    pub ls_colors: LsColors,
+}
+
+impl Theme {
+    // Get ansi codes of a message level
+    pub fn get_level(&self, level: &Level) -> &str {
+        match level {
+            Level::Info => &self.info,
+            Level::Warn => &self.warn,
+            Level::Error => &self.error,
+        }
+    }
+    // Get ansi codes of an entry, preferring the LS_COLORS codes
+    pub fn get_entry(&self, entry: &Entry) -> &str {
+        if let Some(codes) = self.ls_colors.get(entry) {
+            return codes;
+        }
+        match entry.kind {
+            EntryKind::File => &self.file,
+            EntryKind::Dir => &self.dir,
+            EntryKind::Symlink => &self.symlink,
+        }
+    }
+}
+
+// The colors of the LS_COLORS variable by file type and extension
+#[derive(Default)]
+pub struct LsColors {
+    // The codes by file type indicator like `di` or `ex`
+    types: HashMap<String, String>,
+    // The codes by lowercase file name suffix like `.tar.gz`
+    suffixes: Vec<(String, String)>,
+}
+
+impl LsColors {
+    // Parses a value like `di=01;34:*.tar=01;31`
+    pub fn parse(value: &str) -> Self {
+        let mut colors = Self::default();
+        for pair in value.split(':') {
+            let (key, codes) = match pair.split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            match key.strip_prefix('*') {
+                Some(suffix) => colors
+                    .suffixes
+                    .push((suffix.to_lowercase(), codes.to_string())),
+                None => {
+                    colors.types.insert(key.to_string(), codes.to_string());
+                }
+            }
+        }
+        // longer suffixes like `.tar.gz` take precedence over `.gz`
+        colors
+            .suffixes
+            .sort_by_key(|(suffix, _)| std::cmp::Reverse(suffix.len()));
+        colors
+    }
+    // Get codes of an entry by its type and extension the way ls picks them
+    pub fn get(&self, entry: &Entry) -> Option<&str> {
+        let mode = entry.mode;
+        let indicator = match entry.kind {
+            EntryKind::Symlink if entry.broken && self.types.contains_key("or") => "or",
+            EntryKind::Symlink => "ln",
+            EntryKind::Dir if mode & 0o1002 == 0o1002 && self.types.contains_key("tw") => "tw",
+            EntryKind::Dir if mode & 0o0002 != 0 && self.types.contains_key("ow") => "ow",
+            EntryKind::Dir if mode & 0o1000 != 0 && self.types.contains_key("st") => "st",
+            EntryKind::Dir => "di",
+            EntryKind::File => match mode & libc::S_IFMT {
+                libc::S_IFIFO => "pi",
+                libc::S_IFSOCK => "so",
+                libc::S_IFBLK => "bd",
+                libc::S_IFCHR => "cd",
+                _ if mode & 0o4000 != 0 && self.types.contains_key("su") => "su",
+                _ if mode & 0o2000 != 0 && self.types.contains_key("sg") => "sg",
+                _ if mode & 0o0111 != 0 && self.types.contains_key("ex") => "ex",
+                _ => {
+                    let file_name = entry.file_name.to_lowercase();
+                    for (suffix, codes) in &self.suffixes {
+                        if file_name.ends_with(suffix) {
+                            return Some(codes);
+                        }
+                    }
+                    "fi"
+                }
+            },
+        };
+        // symlinks colored like their target fall back to the theme
+        self.types
+            .get(indicator)
+            .map(|codes| codes.as_str())
+            .filter(|codes| *codes != "target")
+    }
+}
+
+// Wraps the text in ansi codes, unless colors are disabled
+pub fn paint(codes: &str, text: &str) -> String {
+    if codes.is_empty() || !console::colors_enabled() {
+        return text.to_string();
+    }
+    format!("\x1b[{}m{}\x1b[0m", codes, text)
+}
+
+// Combines the codes of two styles, where the latter wins
+pub fn combine(codes: &str, other: &str) -> String {
+    match (codes.is_empty(), other.is_empty()) {
+        (true, _) => other.to_string(),
+        (_, true) => codes.to_string(),
+        _ => format!("{};{}", codes, other),
+    }
+}
+
+// Parses a dotted style like `black.on_white.bold` into ansi codes
+pub fn parse_style(style: &str) -> Result<String> {
+    let mut codes = Vec::new();
+    for name in style.split('.').filter(|name| !name.is_empty()) {
+        let (background, color) = match name.strip_prefix("on_") {
+            Some(color) => (true, color),
+            None => (false, name),
+        };
+        let (bright, color) = match color.strip_prefix("bright_") {
+            Some(color) => (true, color),
+            None => (false, color),
+        };
+        let base = match (background, bright) {
+            (false, false) => 30,
+            (true, false) => 40,
+            (false, true) => 90,
+            (true, true) => 100,
+        };
+        if let Some(index) = COLORS.iter().position(|other| *other == color) {
+            codes.push((base + index).to_string());
+        } else if let Ok(index) = color.parse::<u8>() {
+            let prefix = if background { 48 } else { 38 };
+            codes.push(format!("{};5;{}", prefix, index));
+        } else if let Some((_, code)) = ATTRIBUTES.iter().find(|(other, _)| *other == name) {
+            codes.push(code.to_string());
+        } else {
+            return Err(Error::new(&format!("Invalid style '{}'!", style)));
+        }
+    }
+    Ok(codes.join(";"))
+}
@@ -0,0 +1,211 @@
+use chrono::offset::Local;
+use chrono::NaiveDateTime;
+use std::cmp::Reverse;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::ops;
+use crate::ops::Progress;
+use crate::Error;
+use crate::Result;
+
+// The date format used in trash info files
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+pub struct TrashItem {
+    // The name of the entry in the trash
+    pub name: String,
+    // The original path of the entry
+    pub path: PathBuf,
+    // The date the entry was trashed
+    pub deleted: Option<NaiveDateTime>,
+}
+
+impl TrashItem {
+    // Get path of the trashed entry
+    pub fn get_file(&self) -> Result<PathBuf> {
+        Ok(trash_dir()?.join("files").join(&self.name))
+    }
+    // Get path of the trash info file
+    pub fn get_info(&self) -> Result<PathBuf> {
+        Ok(trash_dir()?
+            .join("info")
+            .join(format!("{}.trashinfo", self.name)))
+    }
+}
+
+// Get path of the home trash directory
+pub fn trash_dir() -> Result<PathBuf> {
+    match dirs::data_dir() {
+        Some(data_dir) => Ok(data_dir.join("Trash")),
+        None => Err(Error::new("Unable to determine trash path!")),
+    }
+}
+
+// Moves the entry into the trash and returns the trashed item
+pub fn put(path: &Path) -> Result<TrashItem> {
+    put_with(path, &mut |_, _| Ok(()))
+}
+
+// Moves the entry into the trash and reports the progress, which is only
+// reported if the entry is copied to another filesystem
+pub fn put_with(path: &Path, progress: &mut Progress) -> Result<TrashItem> {
+    let trash_dir = trash_dir()?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    fs::create_dir_all(&files_dir)?;
+    fs::create_dir_all(&info_dir)?;
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name.to_string_lossy().to_string(),
+        None => return Err(Error::new("Invalid path!")),
+    };
+    let deleted = Local::now().naive_local();
+    // creating the info file exclusively reserves the name in the trash,
+    // while an orphaned file without info file takes the name as well
+    let mut counter = 1;
+    let (name, mut info) = loop {
+        let name = match counter {
+            1 => file_name.clone(),
+            _ => format!("{}.{}", file_name, counter),
+        };
+        let info = match files_dir.join(&name).symlink_metadata() {
+            Ok(_) => Err(io::Error::from(io::ErrorKind::AlreadyExists)),
+            Err(_) => OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(info_dir.join(format!("{}.trashinfo", name))),
+        };
+        match info {
+            Ok(info) => break (name, info),
+            Err(_) if counter < 1000 => counter += 1,
+            Err(err) => return Err(err.into()),
+        }
+    };
+    write!(
+        info,
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode(path.as_os_str()),
+        deleted.format(DATE_FORMAT)
+    )?;
+    let item = TrashItem {
+        name,
+        path: path.to_path_buf(),
+        deleted: Some(deleted),
+    };
+    if let Err(err) = ops::rename_with(path, &item.get_file()?, progress) {
+        fs::remove_file(item.get_info()?)?;
+        return Err(err);
+    }
+    Ok(item)
+}
+
+// Lists all items in the trash, most recently trashed first
+pub fn list() -> Result<Vec<TrashItem>> {
+    let info_dir = trash_dir()?.join("info");
+    let mut items = Vec::new();
+    if !info_dir.exists() {
+        return Ok(items);
+    }
+    for dir_entry in fs::read_dir(&info_dir)? {
+        let item = dir_entry?;
+        let file_name = item.file_name().to_string_lossy().to_string();
+        let name = match file_name.strip_suffix(".trashinfo") {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let raw = match fs::read_to_string(item.path()) {
+            Ok(raw) => raw,
+            Err(_) => continue,
+        };
+        let mut path = None;
+        let mut deleted = None;
+        for line in raw.lines() {
+            if let Some(value) = line.strip_prefix("Path=") {
+                path = Some(PathBuf::from(decode(value)));
+            }
+            if let Some(value) = line.strip_prefix("DeletionDate=") {
+                deleted = NaiveDateTime::parse_from_str(value, DATE_FORMAT).ok();
+            }
+        }
+        if let Some(path) = path {
+            items.push(TrashItem {
+                name,
+                path,
+                deleted,
+            });
+        }
+    }
+    items.sort_by_key(|item| Reverse(item.deleted));
+    Ok(items)
+}
+
+// Moves the item back to its original path
+pub fn restore(item: &TrashItem) -> Result<()> {
+    restore_with(item, &mut |_, _| Ok(()))
+}
+
+// Moves the item back to its original path and reports the progress
+pub fn restore_with(item: &TrashItem, progress: &mut Progress) -> Result<()> {
+    if item.path.symlink_metadata().is_ok() {
+        return Err(Error::new(&format!(
+            "'{}' already exists!",
+            item.path.display()
+        )));
+    }
+    if let Some(parent) = item.path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    ops::rename_with(&item.get_file()?, &item.path, progress)?;
+    fs::remove_file(item.get_info()?)?;
+    Ok(())
+}
+
+// Removes the item from the trash permanently
+pub fn purge(item: &TrashItem) -> Result<()> {
+    let file = item.get_file()?;
+    if file.symlink_metadata().is_ok() {
+        ops::remove(&file)?;
+    }
+    fs::remove_file(item.get_info()?)?;
+    Ok(())
+}
+
+// Percent-encodes a path as required by the trash specification
+fn encode(path: &OsStr) -> String {
+    let mut encoded = String::new();
+    for byte in path.as_bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(*byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn decode(value: &str) -> OsString {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    OsStr::from_bytes(&decoded).to_os_string()
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::mem;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+// The largest buffer used for looking up a user or group
+const MAX_BUFFER_SIZE: usize = 1 << 20;
+
+// The names of users and groups which were already looked up
+static USERS: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+static GROUPS: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+
+// Get name of the user, or the uid if the user is unknown
+pub fn get_user_name(uid: u32) -> String {
+    cached(&USERS, uid, lookup_user)
+}
+
+// Get name of the group, or the gid if the group is unknown
+pub fn get_group_name(gid: u32) -> String {
+    cached(&GROUPS, gid, lookup_group)
+}
+
+fn cached(
+    cache: &OnceLock<Mutex<HashMap<u32, String>>>,
+    id: u32,
+    lookup: fn(u32) -> Option<String>,
+) -> String {
+    let mut cache = match cache.get_or_init(Default::default).lock() {
+        Ok(cache) => cache,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    cache
+        .entry(id)
+        .or_insert_with(|| lookup(id).unwrap_or_else(|| id.to_string()))
+        .clone()
+}
+
+fn lookup_user(uid: u32) -> Option<String> {
+    let mut size = 1024;
+    loop {
+        let mut buffer: Vec<libc::c_char> = vec![0; size];
+        let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+        let code =
+            unsafe { libc::getpwuid_r(uid, &mut passwd, buffer.as_mut_ptr(), size, &mut result) };
+        match code {
+            libc::ERANGE if size < MAX_BUFFER_SIZE => size *= 2,
+            0 if !result.is_null() => {
+                let name = unsafe { CStr::from_ptr(passwd.pw_name) };
+                return Some(name.to_string_lossy().to_string());
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn lookup_group(gid: u32) -> Option<String> {
+    let mut size = 1024;
+    loop {
+        let mut buffer: Vec<libc::c_char> = vec![0; size];
+        let mut group: libc::group = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+        let code =
+            unsafe { libc::getgrgid_r(gid, &mut group, buffer.as_mut_ptr(), size, &mut result) };
+        match code {
+            libc::ERANGE if size < MAX_BUFFER_SIZE => size *= 2,
+            0 if !result.is_null() => {
+                let name = unsafe { CStr::from_ptr(group.gr_name) };
+                return Some(name.to_string_lossy().to_string());
+            }
+            _ => return None,
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::opener::Opener;
+use crate::shell;
+
+// The placeholder for the opened files in an app command
+const PLACEHOLDER: &str = "{}";
+
+// Get apps associated with the mime type by the mimeapps.list files,
+// the preferred app first
+pub fn get_apps(mime: &str) -> Vec<Opener> {
+    let mut defaults = Vec::new();
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for path in list_paths() {
+        let raw = match fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(_) => continue,
+        };
+        defaults.extend(get_ids(&raw, "Default Applications", mime));
+        added.extend(get_ids(&raw, "Added Associations", mime));
+        removed.extend(get_ids(&raw, "Removed Associations", mime));
+    }
+    let mut ids: Vec<String> = Vec::new();
+    for id in defaults.into_iter().chain(added) {
+        if !removed.contains(&id) && !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids.iter().filter_map(|id| read_desktop(id)).collect()
+}
+
+// Converts the Exec line of a desktop entry into an opener, where `%f` and `%u`
+// open one file per process and `%F` and `%U` open all files at once
+fn parse_exec(exec: &str, name: &str, icon: Option<&str>, location: &Path) -> Option<Opener> {
+    let mut opener = Opener::default();
+    let mut args = Vec::new();
+    for arg in shell::split(&unescape(exec))? {
+        match arg.as_str() {
+            "%f" | "%u" => args.push(PLACEHOLDER.to_string()),
+            "%F" | "%U" => {
+                opener.multiple = true;
+                args.push(PLACEHOLDER.to_string());
+            }
+            "%i" => {
+                if let Some(icon) = icon {
+                    args.push("--icon".to_string());
+                    args.push(icon.to_string());
+                }
+            }
+            _ => {
+                let arg = expand_codes(&arg, name, location);
+                if !arg.is_empty() {
+                    args.push(arg);
+                }
+            }
+        }
+    }
+    if args.is_empty() {
+        return None;
+    }
+    let quoted: Vec<String> = args.iter().map(|arg| shell::quote(arg)).collect();
+    opener.app = quoted.join(" ");
+    Some(opener)
+}
+
+// Reads the desktop entry with the id from the applications directories
+fn read_desktop(id: &str) -> Option<Opener> {
+    let path = data_dirs()
+        .into_iter()
+        .map(|data_dir| data_dir.join("applications").join(id))
+        .find(|path| path.is_file())?;
+    let raw = fs::read_to_string(&path).ok()?;
+    let group = get_group(&raw, "Desktop Entry");
+    let get = |key: &str| {
+        group
+            .iter()
+            .find(|(other, _)| other == key)
+            .map(|(_, value)| value.as_str())
+    };
+    if get("Hidden") == Some("true") {
+        return None;
+    }
+    let name = get("Name").unwrap_or(id);
+    let mut opener = parse_exec(get("Exec")?, name, get("Icon"), &path)?;
+    opener.gui = get("Terminal") != Some("true");
+    Some(opener)
+}
+
+// Expands the field codes within an argument and drops the deprecated ones
+fn expand_codes(arg: &str, name: &str, location: &Path) -> String {
+    let mut expanded = String::new();
+    let mut chars = arg.chars();
+    while let Some(char) = chars.next() {
+        if char != '%' {
+            expanded.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('f') | Some('u') => expanded.push_str(PLACEHOLDER),
+            Some('c') => expanded.push_str(name),
+            Some('k') => expanded.push_str(&location.to_string_lossy()),
+            Some('%') => expanded.push('%'),
+            _ => (),
+        }
+    }
+    expanded
+}
+
+// Replaces the escape sequences of a desktop entry value
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            // other sequences are kept for splitting the arguments
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+// Get desktop ids listed for the mime type in a group of a mimeapps.list file
+fn get_ids(raw: &str, group: &str, mime: &str) -> Vec<String> {
+    match get_group(raw, group)
+        .into_iter()
+        .find(|(key, _)| key == mime)
+    {
+        Some((_, value)) => value
+            .split(';')
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// Get key value pairs of a group in an ini-like file
+fn get_group(raw: &str, group: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut current = false;
+    for line in raw.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            current = &line[1..line.len() - 1] == group;
+            continue;
+        }
+        if !current {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            pairs.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    pairs
+}
+
+// Get paths of the mimeapps.list files, the most important first
+fn list_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(config_dir) = dirs::config_dir() {
+        paths.push(config_dir.join("mimeapps.list"));
+    }
+    for config_dir in get_dirs("XDG_CONFIG_DIRS", "/etc/xdg") {
+        paths.push(config_dir.join("mimeapps.list"));
+    }
+    for data_dir in data_dirs() {
+        paths.push(data_dir.join("applications").join("mimeapps.list"));
+    }
+    // the deprecated defaults.list is still written by some distributions
+    for data_dir in data_dirs() {
+        paths.push(data_dir.join("applications").join("defaults.list"));
+    }
+    paths
+}
+
+// Get data directories, the most important first
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(data_dir) = dirs::data_dir() {
+        dirs.push(data_dir);
+    }
+    dirs.extend(get_dirs("XDG_DATA_DIRS", "/usr/local/share:/usr/share"));
+    dirs
+}
+
+fn get_dirs(var: &str, default: &str) -> Vec<PathBuf> {
+    let value = match env::var(var) {
+        Ok(value) if !value.is_empty() => value,
+        _ => default.to_string(),
+    };
+    value
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}